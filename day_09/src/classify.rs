@@ -0,0 +1,370 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use anyhow::{bail, Result};
+
+use crate::{extrapolate_binomial, Fraction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Class {
+    Constant,
+    Arithmetic,
+    Polynomial(usize),
+    Geometric,
+    Recurrence(usize),
+    Unclassified,
+}
+
+impl Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Class::Constant => write!(f, "constant"),
+            Class::Arithmetic => write!(f, "arithmetic"),
+            Class::Polynomial(degree) => write!(f, "polynomial of degree {degree}"),
+            Class::Geometric => write!(f, "geometric"),
+            Class::Recurrence(order) => write!(f, "linear recurrence of order {order}"),
+            Class::Unclassified => write!(f, "unclassified"),
+        }
+    }
+}
+
+/// Rule that generates a sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    Constant(i64),
+    Arithmetic {
+        step: i64,
+    },
+    Polynomial {
+        degree: usize,
+    },
+    Geometric {
+        ratio: Fraction,
+    },
+    /// `a(n) = coefficients[0] * a(n - 1) + coefficients[1] * a(n - 2) + ...`
+    Recurrence {
+        coefficients: Vec<Fraction>,
+    },
+    /// No rule is confirmed by the values, the difference pyramid is used as
+    /// a fallback.
+    Unclassified,
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rule::Constant(value) => write!(f, "constant {value}"),
+            Rule::Arithmetic { step } => write!(f, "arithmetic with step {step}"),
+            Rule::Polynomial { degree } => write!(f, "polynomial of degree {degree}"),
+            Rule::Geometric { ratio } => write!(f, "geometric with ratio {ratio}"),
+            Rule::Recurrence { coefficients } => {
+                write!(f, "a(n) =")?;
+                for (i, coefficient) in coefficients.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { " +" };
+                    write!(f, "{sep} {coefficient} * a(n - {})", i + 1)?;
+                }
+                Ok(())
+            }
+            Rule::Unclassified => write!(f, "unclassified"),
+        }
+    }
+}
+
+impl Rule {
+    pub fn class(&self) -> Class {
+        match self {
+            Rule::Constant(_) => Class::Constant,
+            Rule::Arithmetic { .. } => Class::Arithmetic,
+            Rule::Polynomial { degree } => Class::Polynomial(*degree),
+            Rule::Geometric { .. } => Class::Geometric,
+            Rule::Recurrence { coefficients } => Class::Recurrence(coefficients.len()),
+            Rule::Unclassified => Class::Unclassified,
+        }
+    }
+
+    /// Number of values needed to pin the rule down.
+    fn parameters(&self) -> usize {
+        match self {
+            Rule::Constant(_) => 1,
+            Rule::Arithmetic { .. } | Rule::Geometric { .. } => 2,
+            Rule::Polynomial { degree } => degree + 1,
+            Rule::Recurrence { coefficients } => 2 * coefficients.len(),
+            Rule::Unclassified => usize::MAX,
+        }
+    }
+
+    /// Extrapolates the element following `values`, or the one preceding
+    /// them for part 2. Where the rule does not yield an integer there, the
+    /// difference pyramid is used instead.
+    pub fn extrapolate(&self, values: &[i64], is_part_2: bool) -> Result<i64> {
+        if values.is_empty() {
+            bail!("empty sequence");
+        }
+
+        let exact = if is_part_2 {
+            self.prev(values)?
+        } else {
+            self.next(values)?
+        };
+
+        match exact {
+            Some(value) if value.den == 1 => Ok(i64::try_from(value.num)?),
+            _ if is_part_2 => Ok(extrapolate_binomial(
+                values.iter().rev().copied(),
+                values.len(),
+            )),
+            _ => Ok(extrapolate_binomial(values.iter().copied(), values.len())),
+        }
+    }
+
+    /// Element following `values` by the rule, if it has one.
+    fn next(&self, values: &[i64]) -> Result<Option<Fraction>> {
+        let last = Fraction::from(values[values.len() - 1] as i128);
+
+        Ok(Some(match self {
+            Rule::Constant(value) => Fraction::from(*value as i128),
+            Rule::Arithmetic { step } => last.add(Fraction::from(*step as i128))?,
+            Rule::Polynomial { .. } | Rule::Unclassified => return Ok(None),
+            Rule::Geometric { ratio } => last.mul(*ratio)?,
+            Rule::Recurrence { coefficients } => {
+                let mut sum = Fraction::from(0);
+                for (coefficient, value) in coefficients.iter().zip(values.iter().rev()) {
+                    sum = sum.add(coefficient.mul(Fraction::from(*value as i128))?)?;
+                }
+                sum
+            }
+        }))
+    }
+
+    /// Element preceding `values` by running the rule backward, if it has
+    /// one.
+    fn prev(&self, values: &[i64]) -> Result<Option<Fraction>> {
+        let first = Fraction::from(values[0] as i128);
+
+        Ok(Some(match self {
+            Rule::Constant(value) => Fraction::from(*value as i128),
+            Rule::Arithmetic { step } => first.sub(Fraction::from(*step as i128))?,
+            Rule::Polynomial { .. } | Rule::Unclassified => return Ok(None),
+            Rule::Geometric { ratio } => first.div(*ratio)?,
+            Rule::Recurrence { coefficients } => {
+                // The equation for `values[order - 1]` reaches back to the
+                // preceding element, weighted by the last coefficient.
+                let order = coefficients.len();
+                let weight = coefficients[order - 1];
+                if weight.num == 0 {
+                    return Ok(None);
+                }
+
+                let mut rest = Fraction::from(values[order - 1] as i128);
+                for (coefficient, value) in
+                    coefficients.iter().zip(values[..order - 1].iter().rev())
+                {
+                    rest = rest.sub(coefficient.mul(Fraction::from(*value as i128))?)?;
+                }
+                rest.div(weight)?
+            }
+        }))
+    }
+}
+
+/// Finds the rule with the fewest parameters that generates `values` exactly
+/// and is confirmed by at least one value not needed to determine it.
+pub fn classify(values: &[i64]) -> Rule {
+    let mut best = Rule::Unclassified;
+
+    if let Some(degree) = polynomial_degree(values) {
+        best = match degree {
+            0 => Rule::Constant(values[0]),
+            1 => Rule::Arithmetic {
+                step: values[1] - values[0],
+            },
+            degree => Rule::Polynomial { degree },
+        };
+    }
+
+    if let Some(ratio) = geometric_ratio(values) {
+        let rule = Rule::Geometric { ratio };
+        if rule.parameters() < best.parameters() {
+            best = rule;
+        }
+    }
+
+    let mut order = 2;
+    while 2 * order < values.len() && 2 * order < best.parameters() {
+        if let Some(coefficients) = recurrence(values, order) {
+            best = Rule::Recurrence { coefficients };
+            break;
+        }
+        order += 1;
+    }
+
+    best
+}
+
+/// Degree of the polynomial through `values`, if a layer of the difference
+/// pyramid turns all zero.
+fn polynomial_degree(values: &[i64]) -> Option<usize> {
+    let mut layer = values.to_vec();
+
+    for degree in 0..values.len().saturating_sub(1) {
+        layer = layer
+            .windows(2)
+            .map(|pair| pair[1].checked_sub(pair[0]))
+            .collect::<Option<_>>()?;
+
+        if layer.iter().all(|diff| *diff == 0) {
+            return Some(degree);
+        }
+    }
+
+    None
+}
+
+fn geometric_ratio(values: &[i64]) -> Option<Fraction> {
+    if values.len() < 3 || values.contains(&0) {
+        return None;
+    }
+
+    let ratio = Fraction::new(values[1] as i128, values[0] as i128);
+    values
+        .windows(2)
+        .all(|pair| Fraction::new(pair[1] as i128, pair[0] as i128) == ratio)
+        .then_some(ratio)
+}
+
+/// Solves for the coefficients of a linear recurrence of the given order
+/// with Gaussian elimination and checks them against the remaining values.
+fn recurrence(values: &[i64], order: usize) -> Option<Vec<Fraction>> {
+    let value = |i: usize| Fraction::from(values[i] as i128);
+
+    // Row `r` is the equation for `a(order + r)`.
+    let mut matrix: Vec<Vec<Fraction>> = (0..order)
+        .map(|r| {
+            let mut row: Vec<Fraction> = (1..=order).map(|j| value(order + r - j)).collect();
+            row.push(value(order + r));
+            row
+        })
+        .collect();
+
+    for col in 0..order {
+        let pivot = (col..order).find(|row| matrix[*row][col].num != 0)?;
+        matrix.swap(col, pivot);
+
+        let pivot_row = matrix[col].clone();
+        for (r, row) in matrix.iter_mut().enumerate() {
+            if r == col || row[col].num == 0 {
+                continue;
+            }
+
+            let factor = row[col].div(pivot_row[col]).ok()?;
+            for (entry, pivot) in row.iter_mut().zip(&pivot_row).skip(col) {
+                *entry = entry.sub(factor.mul(*pivot).ok()?).ok()?;
+            }
+        }
+    }
+
+    let coefficients: Vec<Fraction> = (0..order)
+        .map(|row| matrix[row][order].div(matrix[row][row]).ok())
+        .collect::<Option<_>>()?;
+
+    for i in 2 * order..values.len() {
+        let mut sum = Fraction::from(0);
+        for (j, coefficient) in coefficients.iter().enumerate() {
+            sum = sum.add(coefficient.mul(value(i - j - 1)).ok()?).ok()?;
+        }
+
+        if sum != value(i) {
+            return None;
+        }
+    }
+
+    Some(coefficients)
+}
+
+/// Input lines grouped by the class of their sequence.
+#[derive(Debug, Default)]
+pub struct Report {
+    groups: BTreeMap<Class, Vec<usize>>,
+}
+
+impl Report {
+    pub fn add(&mut self, class: Class, line: usize) {
+        self.groups.entry(class).or_default().push(line);
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (class, lines) in &self.groups {
+            let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+            writeln!(f, "{class}: {} lines ({})", lines.len(), lines.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_classify() {
+    let cases: [(&[i64], Rule, i64, i64); 8] = [
+        (&[4, 4, 4], Rule::Constant(4), 4, 4),
+        (&[0, 3, 6, 9, 12, 15], Rule::Arithmetic { step: 3 }, 18, -3),
+        (
+            &[1, 3, 6, 10, 15, 21],
+            Rule::Polynomial { degree: 2 },
+            28,
+            0,
+        ),
+        (
+            &[3, 6, 12, 24, 48],
+            Rule::Geometric {
+                ratio: Fraction::from(2),
+            },
+            96,
+            3,
+        ),
+        (
+            &[64, 32, 16, 8],
+            Rule::Geometric {
+                ratio: Fraction::new(1, 2),
+            },
+            4,
+            128,
+        ),
+        // 1/2 before the start is no integer, so the pyramid takes over.
+        (
+            &[1, 2, 4, 8, 16],
+            Rule::Geometric {
+                ratio: Fraction::from(2),
+            },
+            32,
+            1,
+        ),
+        (
+            &[1, 1, 2, 3, 5, 8, 13, 21],
+            Rule::Recurrence {
+                coefficients: vec![Fraction::from(1), Fraction::from(1)],
+            },
+            34,
+            0,
+        ),
+        (&[3, -1, 4, 1, -5], Rule::Unclassified, 13, 55),
+    ];
+
+    for (values, rule, next, prev) in cases {
+        let classified = classify(values);
+        assert_eq!(classified, rule);
+        assert_eq!(
+            classified.extrapolate(values, false).unwrap(),
+            next,
+            "{rule}"
+        );
+        assert_eq!(
+            classified.extrapolate(values, true).unwrap(),
+            prev,
+            "{rule}"
+        );
+    }
+
+    let values = [64, 32, 16, 8, 4, 2, 1];
+    assert_eq!(classify(&values).extrapolate(&values, false).unwrap(), 1);
+    assert!(classify(&[]).extrapolate(&[], true).is_err());
+}
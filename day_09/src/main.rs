@@ -1,13 +1,21 @@
 use std::{
+    fmt::Display,
     fs::File,
-    io::{BufRead, BufReader, Lines},
+    io::{self, BufRead, BufReader, Lines},
     num::ParseIntError,
     path::Path,
     str::FromStr,
-    vec,
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex, PoisonError,
+    },
+    thread, vec,
 };
 
-use anyhow::{Error, Result};
+use anyhow::{bail, Context, Error, Result};
+use classify::{classify, Report};
+
+mod classify;
 
 fn read_lines<P>(filename: P) -> Result<Lines<BufReader<File>>>
 where
@@ -20,26 +28,101 @@ where
 #[derive(Debug)]
 struct Calculator {
     vals: Vec<Vec<i64>>,
+    extrapolated: bool,
+    backward: bool,
 }
 
 impl FromStr for Calculator {
     type Err = Error;
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        let first_line: Result<Vec<i64>, ParseIntError> =
-            s.split_whitespace().map(|s| s.parse::<i64>()).collect();
+        let first_line: Result<Vec<Option<i64>>, ParseIntError> = s
+            .split_whitespace()
+            .map(|s| match s {
+                "_" => Ok(None),
+                s => s.parse::<i64>().map(Some),
+            })
+            .collect();
 
         Ok(Self {
-            vals: vec![first_line?],
+            vals: vec![fill_gaps(&first_line?)?],
+            extrapolated: false,
+            backward: false,
         })
     }
 }
 
+/// Renders the difference pyramid like the puzzle description does.
+///
+/// Extrapolated values are highlighted with ANSI escapes, or with brackets
+/// when formatted with `{:#}`. Backward extrapolation is shown with the new
+/// values prepended on the left.
+impl Display for Calculator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plain = f.alternate();
+
+        // The layers of a backward extrapolation are built from the reversed
+        // sequence, so they are reversed and every other one has its signs
+        // flipped.
+        let layers: Vec<Vec<i64>> = self
+            .vals
+            .iter()
+            .enumerate()
+            .map(|(depth, layer)| match (self.backward, depth % 2) {
+                (false, _) => layer.clone(),
+                (true, 0) => layer.iter().rev().copied().collect(),
+                (true, _) => layer.iter().rev().map(|val| -val).collect(),
+            })
+            .collect();
+
+        let brackets = if plain && self.extrapolated { 2 } else { 0 };
+        let width = layers
+            .iter()
+            .flatten()
+            .map(|val| val.to_string().len() + brackets)
+            .max()
+            .unwrap_or(0);
+        let cell = (width + 2).next_multiple_of(2);
+
+        for (depth, layer) in layers.iter().enumerate() {
+            let mut line = " ".repeat(depth * cell / 2);
+
+            let highlighted = match (self.extrapolated, self.backward) {
+                (false, _) => None,
+                (true, false) => Some(layer.len() - 1),
+                (true, true) => Some(0),
+            };
+
+            for (i, val) in layer.iter().enumerate() {
+                let offset = if i == 0 { width } else { cell };
+
+                if Some(i) != highlighted {
+                    line += &format!("{val:>offset$}");
+                } else if plain {
+                    line += &format!("{:>offset$}", format!("[{val}]"));
+                } else {
+                    line += &format!("\x1b[1;32m{val:>offset$}\x1b[0m");
+                }
+            }
+
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Calculator {
-    fn calculate(&mut self, is_part_2: bool) -> i64 {
+    fn calculate(&mut self, is_part_2: bool) -> Result<i64> {
+        if self.vals[0].is_empty() {
+            bail!("empty sequence");
+        }
+
         if is_part_2 {
             self.vals[0].reverse();
         }
+        self.backward = is_part_2;
+        self.extrapolated = true;
 
         let mut current_layer = 0;
         loop {
@@ -76,21 +159,653 @@ impl Calculator {
             current_layer -= 1;
         }
 
-        *self.vals[0].last().unwrap()
+        Ok(*self.vals[0].last().unwrap())
+    }
+
+    fn calculate_binomial(&self, is_part_2: bool) -> Result<i64> {
+        if self.vals[0].is_empty() {
+            bail!("empty sequence");
+        }
+
+        Ok(if is_part_2 {
+            extrapolate_binomial(self.vals[0].iter().rev().copied(), self.vals[0].len())
+        } else {
+            extrapolate_binomial(self.vals[0].iter().copied(), self.vals[0].len())
+        })
+    }
+}
+
+/// Infers missing interior values by fitting the polynomial of minimum degree
+/// through the known values.
+///
+/// There is exactly one polynomial of degree `k - 1` through `k` known values,
+/// so the fit is always determined, if only by the known values themselves.
+fn fill_gaps(values: &[Option<i64>]) -> Result<Vec<i64>> {
+    let known: Vec<(i128, i128)> = values
+        .iter()
+        .enumerate()
+        .filter_map(|(x, y)| y.map(|y| (x as i128, y as i128)))
+        .collect();
+
+    if known.len() == values.len() {
+        return Ok(values.iter().flatten().copied().collect());
+    }
+
+    if values.first().is_some_and(Option::is_none) || values.last().is_some_and(Option::is_none) {
+        bail!("only interior values can be missing");
+    }
+
+    let mut degree = 0;
+    while degree + 1 < known.len() {
+        let basis = &known[..=degree];
+        let mut fits = true;
+        for &(x, y) in &known[degree + 1..] {
+            if interpolate(basis, x)? != Fraction::from(y) {
+                fits = false;
+                break;
+            }
+        }
+
+        if fits {
+            break;
+        }
+        degree += 1;
+    }
+
+    let basis = &known[..=degree];
+    let mut filled = Vec::with_capacity(values.len());
+
+    for (x, y) in values.iter().enumerate() {
+        match y {
+            Some(y) => filled.push(*y),
+            None => {
+                let value = interpolate(basis, x as i128)?;
+                if value.den != 1 {
+                    bail!("value at position {x} is not an integer: {value}");
+                }
+                filled.push(i64::try_from(value.num)?);
+            }
+        }
+    }
+
+    Ok(filled)
+}
+
+/// Evaluates the Lagrange polynomial through `points` at `x`.
+fn interpolate(points: &[(i128, i128)], x: i128) -> Result<Fraction> {
+    let mut sum = Fraction::from(0);
+
+    for (i, &(xi, yi)) in points.iter().enumerate() {
+        let mut term = Fraction::from(yi);
+        for (j, &(xj, _)) in points.iter().enumerate() {
+            if i != j {
+                term = term.mul(Fraction::new(x - xj, xi - xj))?;
+            }
+        }
+        sum = sum.add(term)?;
+    }
+
+    Ok(sum)
+}
+
+/// Exact rational number, always stored in lowest terms with a positive
+/// denominator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Fraction {
+    num: i128,
+    den: i128,
+}
+
+impl Fraction {
+    fn new(num: i128, den: i128) -> Self {
+        let mut a = num.abs();
+        let mut b = den.abs();
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+
+        let sign = den.signum();
+        Self {
+            num: sign * num / a,
+            den: sign * den / a,
+        }
+    }
+
+    fn mul(self, other: Self) -> Result<Self> {
+        let num = self.num.checked_mul(other.num);
+        let den = self.den.checked_mul(other.den);
+
+        match (num, den) {
+            (Some(num), Some(den)) => Ok(Self::new(num, den)),
+            _ => bail!("overflow in fraction arithmetic"),
+        }
+    }
+
+    fn add(self, other: Self) -> Result<Self> {
+        let num = self
+            .num
+            .checked_mul(other.den)
+            .zip(other.num.checked_mul(self.den))
+            .and_then(|(a, b)| a.checked_add(b));
+        let den = self.den.checked_mul(other.den);
+
+        match (num, den) {
+            (Some(num), Some(den)) => Ok(Self::new(num, den)),
+            _ => bail!("overflow in fraction arithmetic"),
+        }
+    }
+
+    fn sub(self, other: Self) -> Result<Self> {
+        self.add(Self::new(-other.num, other.den))
+    }
+
+    fn div(self, other: Self) -> Result<Self> {
+        if other.num == 0 {
+            bail!("division by zero");
+        }
+        self.mul(Self::new(other.den, other.num))
+    }
+}
+
+impl From<i128> for Fraction {
+    fn from(value: i128) -> Self {
+        Self { num: value, den: 1 }
+    }
+}
+
+impl Display for Fraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+/// Extrapolates a sequence while only keeping the last element of every
+/// layer of the difference pyramid.
+///
+/// Layers that have been zero so far are not stored, so polynomial sequences
+/// of degree `d` need `O(d)` memory and `O(n * d)` time.
+#[derive(Debug, Default)]
+struct StreamingCalculator {
+    tails: Vec<i64>,
+    layers: usize,
+    prev: i64,
+}
+
+impl FromStr for StreamingCalculator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut calculator = Self::default();
+
+        // Gaps can only be filled once the whole line is known, so such
+        // lines are read like the pyramid reads them.
+        if s.split_whitespace().any(|value| value == "_") {
+            for value in s.parse::<Calculator>()?.vals.swap_remove(0) {
+                calculator.push(value);
+            }
+        } else {
+            for value in s.split_whitespace() {
+                calculator.push(value.parse()?);
+            }
+        }
+
+        Ok(calculator)
+    }
+}
+
+impl StreamingCalculator {
+    fn push(&mut self, value: i64) {
+        let mut carry = value;
+
+        for tail in &mut self.tails {
+            let diff = carry.wrapping_sub(*tail);
+            *tail = carry;
+            carry = diff;
+        }
+
+        if carry != 0 {
+            // All layers below the stored ones are zero so far, so each of
+            // them (and the newly started layer) now ends in `carry`.
+            self.tails.resize(self.layers + 1, carry);
+
+            // The new layer has a single element, which is its first one.
+            if self.layers.is_multiple_of(2) {
+                self.prev = self.prev.wrapping_add(carry);
+            } else {
+                self.prev = self.prev.wrapping_sub(carry);
+            }
+        }
+
+        self.layers += 1;
+    }
+
+    fn calculate(&self, is_part_2: bool) -> Result<i64> {
+        if self.layers == 0 {
+            bail!("empty sequence");
+        }
+
+        Ok(if is_part_2 {
+            self.prev
+        } else {
+            self.tails
+                .iter()
+                .fold(0, |acc, tail| acc.wrapping_add(*tail))
+        })
+    }
+}
+
+/// Computes the next element of `values` (of length `n`) directly as
+/// `sum((-1)^(n - 1 - k) * C(n, k) * values[k])`.
+///
+/// The arithmetic is done modulo 2^64, so the result is exact whenever it
+/// fits into an `i64`, even though the binomial weights do not.
+fn extrapolate_binomial(values: impl Iterator<Item = i64>, n: usize) -> i64 {
+    // C(n, k) is tracked as `odd * 2^twos`, since only odd numbers can be
+    // inverted modulo 2^64.
+    let mut odd: u64 = 1;
+    let mut twos = 0;
+    let mut sum: u64 = 0;
+
+    for (k, value) in values.enumerate() {
+        if k > 0 {
+            let factor = (n - k + 1) as u64;
+            twos += factor.trailing_zeros();
+            odd = odd.wrapping_mul(factor >> factor.trailing_zeros());
+
+            let divisor = k as u64;
+            twos -= divisor.trailing_zeros();
+            odd = odd.wrapping_mul(inverse(divisor >> divisor.trailing_zeros()));
+        }
+
+        let weight = if twos >= 64 { 0 } else { odd << twos };
+        let term = weight.wrapping_mul(value as u64);
+
+        if (n - 1 - k).is_multiple_of(2) {
+            sum = sum.wrapping_add(term);
+        } else {
+            sum = sum.wrapping_sub(term);
+        }
+    }
+
+    sum as i64
+}
+
+/// Multiplicative inverse of an odd number modulo 2^64 (Newton's method).
+fn inverse(odd: u64) -> u64 {
+    let mut inv = odd;
+    for _ in 0..5 {
+        inv = inv.wrapping_mul(2u64.wrapping_sub(odd.wrapping_mul(inv)));
+    }
+    inv
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Pyramid,
+    Streaming,
+    Binomial,
+}
+
+fn extrapolate(line: &str, mode: Mode, is_part_2: bool) -> Result<i64> {
+    Ok(match mode {
+        Mode::Pyramid => line.parse::<Calculator>()?.calculate(is_part_2)?,
+        Mode::Streaming => line.parse::<StreamingCalculator>()?.calculate(is_part_2)?,
+        Mode::Binomial => line.parse::<Calculator>()?.calculate_binomial(is_part_2)?,
+    })
+}
+
+/// Closes the receiver shared by the workers when one of them panics, so
+/// that the producer stops instead of blocking on a full channel.
+struct CloseOnPanic<'a, T>(&'a Mutex<Option<Receiver<T>>>);
+
+impl<T> Drop for CloseOnPanic<'_, T> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner).take();
+        }
+    }
+}
+
+/// Extrapolates every line on a pool of `workers` threads.
+///
+/// Lines are handed out in batches. If several lines fail, the error of the
+/// first one is reported, so the result does not depend on the scheduling.
+fn extrapolate_parallel(
+    lines: impl Iterator<Item = io::Result<String>>,
+    workers: usize,
+    mode: Mode,
+    is_part_2: bool,
+) -> Result<i64> {
+    const BATCH_SIZE: usize = 4096;
+
+    let (sender, receiver) = mpsc::sync_channel::<Vec<(usize, String)>>(2 * workers);
+    let receiver = &Mutex::new(Some(receiver));
+
+    let (results, read_error) = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(move || {
+                    let _close = CloseOnPanic(receiver);
+                    let mut sum = 0;
+                    let mut error: Option<(usize, Error)> = None;
+
+                    // Batches arrive in order, so once a line failed, all
+                    // following lines of this worker can be skipped.
+                    loop {
+                        let batch = receiver.lock().unwrap().as_ref().map(Receiver::recv);
+                        let Some(Ok(batch)) = batch else {
+                            break;
+                        };
+
+                        for (number, line) in batch {
+                            if error.is_some() {
+                                break;
+                            }
+
+                            match extrapolate(&line, mode, is_part_2) {
+                                Ok(value) => sum += value,
+                                Err(e) => error = Some((number, e)),
+                            }
+                        }
+                    }
+
+                    (sum, error)
+                })
+            })
+            .collect();
+
+        let mut read_error = None;
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+        for (number, line) in (1..).zip(lines) {
+            match line {
+                Ok(line) => batch.push((number, line)),
+                Err(e) => {
+                    read_error = Some((number, e.into()));
+                    break;
+                }
+            }
+
+            if batch.len() == BATCH_SIZE {
+                let full = std::mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE));
+                if sender.send(full).is_err() {
+                    break;
+                }
+            }
+        }
+        if !batch.is_empty() {
+            let _ = sender.send(batch);
+        }
+        drop(sender);
+
+        let results: Vec<_> = handles
+            .into_iter()
+            .map(|handle| handle.join().expect("worker panicked"))
+            .collect();
+
+        (results, read_error)
+    });
+
+    let mut sum = 0;
+    let mut first_error: Option<(usize, Error)> = read_error;
+
+    for (partial, error) in results {
+        sum += partial;
+
+        if let Some((number, e)) = error {
+            if first_error
+                .as_ref()
+                .is_none_or(|(first, _)| number < *first)
+            {
+                first_error = Some((number, e));
+            }
+        }
+    }
+
+    match first_error {
+        Some((number, e)) => Err(e.context(format!("line {number}"))),
+        None => Ok(sum),
     }
 }
 
 fn main() -> Result<(), Error> {
+    let mut mode = Mode::Pyramid;
+    let mut show = false;
+    let mut classify_lines = false;
+    let mut workers = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--streaming" => mode = Mode::Streaming,
+            "--binomial" => mode = Mode::Binomial,
+            "--show" => show = true,
+            "--classify" => classify_lines = true,
+            "--workers" => {
+                let count: usize = args.next().context("missing worker count")?.parse()?;
+                if count == 0 {
+                    bail!("at least one worker is needed");
+                }
+                workers = Some(count);
+            }
+            _ => bail!("unknown argument {arg}"),
+        }
+    }
+
+    if let Some(workers) = workers {
+        if show || classify_lines {
+            bail!("--workers cannot be combined with --show or --classify");
+        }
+
+        let sum = extrapolate_parallel(read_lines("input")?, workers, mode, true)?;
+        println!("{sum}");
+
+        return Ok(());
+    }
+
     let mut sum = 0;
+    let mut report = Report::default();
 
-    for line in read_lines("input")? {
+    for (number, line) in read_lines("input")?.enumerate() {
         let line = line?;
+        let context = || format!("line {}", number + 1);
 
-        let mut c: Calculator = line.parse()?;
-        sum += c.calculate(true);
+        if classify_lines {
+            let values = line
+                .parse::<Calculator>()
+                .with_context(context)?
+                .vals
+                .swap_remove(0);
+            let rule = classify(&values);
+            report.add(rule.class(), number + 1);
+            sum += rule.extrapolate(&values, true).with_context(context)?;
+        } else if show {
+            let mut c: Calculator = line.parse().with_context(context)?;
+            sum += c.calculate(true).with_context(context)?;
+            println!("{c}");
+        } else {
+            sum += extrapolate(&line, mode, true).with_context(context)?;
+        }
     }
 
+    if classify_lines {
+        print!("{report}");
+    }
     println!("{sum}");
 
     Ok(())
 }
+
+#[cfg(test)]
+const EXAMPLE: [&str; 3] = ["0 3 6 9 12 15", "1 3 6 10 15 21", "10 13 16 21 30 45"];
+
+#[test]
+fn test_modes_agree() {
+    for mode in [Mode::Pyramid, Mode::Streaming, Mode::Binomial] {
+        let part_1: i64 = EXAMPLE
+            .iter()
+            .map(|line| extrapolate(line, mode, false).unwrap())
+            .sum();
+        let part_2: i64 = EXAMPLE
+            .iter()
+            .map(|line| extrapolate(line, mode, true).unwrap())
+            .sum();
+
+        assert_eq!(part_1, 114, "{mode:?}");
+        assert_eq!(part_2, 2, "{mode:?}");
+    }
+
+    // Not a polynomial of low degree, so every layer is needed.
+    let line = "3 -1 4 1 -5 9 2 -6 5 3";
+    let expected = extrapolate(line, Mode::Pyramid, false).unwrap();
+    assert_eq!(extrapolate(line, Mode::Streaming, false).unwrap(), expected);
+    assert_eq!(extrapolate(line, Mode::Binomial, false).unwrap(), expected);
+    let expected = extrapolate(line, Mode::Pyramid, true).unwrap();
+    assert_eq!(extrapolate(line, Mode::Streaming, true).unwrap(), expected);
+    assert_eq!(extrapolate(line, Mode::Binomial, true).unwrap(), expected);
+
+    for mode in [Mode::Pyramid, Mode::Streaming, Mode::Binomial] {
+        let error = extrapolate("", mode, false).unwrap_err();
+        assert_eq!(error.to_string(), "empty sequence", "{mode:?}");
+
+        let line = "1 _ 9 16 _ 36 49";
+        assert_eq!(extrapolate(line, mode, false).unwrap(), 64, "{mode:?}");
+        assert_eq!(extrapolate(line, mode, true).unwrap(), 0, "{mode:?}");
+    }
+}
+
+#[test]
+fn test_display() {
+    let mut c: Calculator = EXAMPLE[0].parse().unwrap();
+    assert_eq!(format!("{c:#}"), " 0   3   6   9  12  15\n");
+
+    c.calculate(false).unwrap();
+    assert_eq!(
+        format!("{c:#}"),
+        concat!(
+            "   0     3     6     9    12    15  [18]\n",
+            "      3     3     3     3     3   [3]\n",
+            "         0     0     0     0   [0]\n",
+        )
+    );
+
+    let mut c: Calculator = EXAMPLE[2].parse().unwrap();
+    c.calculate(true).unwrap();
+    assert_eq!(
+        format!("{c:#}"),
+        concat!(
+            " [5]    10    13    16    21    30    45\n",
+            "    [5]     3     3     5     9    15\n",
+            "      [-2]     0     2     4     6\n",
+            "          [2]     2     2     2\n",
+            "             [0]     0     0\n",
+        )
+    );
+}
+
+#[test]
+fn test_fill_gaps() {
+    let c: Calculator = "1 _ 9 16 _ 36 49".parse().unwrap();
+    assert_eq!(c.vals[0], vec![1, 4, 9, 16, 25, 36, 49]);
+
+    let c: Calculator = "10 13 16 21 30 _ _ 101".parse().unwrap();
+    assert_eq!(c.vals[0], vec![10, 13, 16, 21, 30, 45, 68, 101]);
+    assert!("10 13 _ 30".parse::<Calculator>().is_err());
+    assert!("0 _ 1 _ 2 _ 3".parse::<Calculator>().is_err());
+
+    assert!("_ 3 6 9".parse::<Calculator>().is_err());
+    assert!("0 3 _ 9 _".parse::<Calculator>().is_err());
+
+    // Fits that no further value confirms are still unique.
+    let c: Calculator = "1 _ 3".parse().unwrap();
+    assert_eq!(c.vals[0], vec![1, 2, 3]);
+    let c: Calculator = "0 _ 4 9".parse().unwrap();
+    assert_eq!(c.vals[0], vec![0, 1, 4, 9]);
+    let error = "0 _ 1 2".parse::<Calculator>().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "value at position 1 is not an integer: 1/3"
+    );
+}
+
+#[test]
+fn test_extrapolate_parallel() {
+    let lines: Vec<String> = (0..10_000)
+        .map(|i: i64| format!("{i} {} {} {}", 2 * i, 4 * i + 1, 8 * i + 3))
+        .collect();
+    let expected: i64 = lines
+        .iter()
+        .map(|line| extrapolate(line, Mode::Pyramid, false).unwrap())
+        .sum();
+
+    for workers in [1, 3, 8] {
+        let lines = lines.iter().cloned().map(Ok);
+        let sum = extrapolate_parallel(lines, workers, Mode::Pyramid, false).unwrap();
+        assert_eq!(sum, expected);
+    }
+
+    let mut lines = lines;
+    lines[9_000] = String::from("1 x 3");
+    lines[5_000] = String::from("1 2 y");
+    for workers in [1, 3, 8] {
+        let error =
+            extrapolate_parallel(lines.iter().cloned().map(Ok), workers, Mode::Pyramid, false)
+                .unwrap_err();
+        assert_eq!(error.to_string(), "line 5001");
+    }
+
+    lines[0] = String::new();
+    let error =
+        extrapolate_parallel(lines.iter().cloned().map(Ok), 3, Mode::Pyramid, false).unwrap_err();
+    assert_eq!(format!("{error:#}"), "line 1: empty sequence");
+
+    // A panicking worker must not leave the producer blocked on a full
+    // channel. The difference overflows, which panics in debug builds.
+    lines[0] = format!("{} {}", i64::MIN, i64::MAX);
+    for workers in [1, 3] {
+        let lines: Vec<String> = lines
+            .iter()
+            .cycle()
+            .take(10 * lines.len())
+            .cloned()
+            .collect();
+        let result = std::panic::catch_unwind(move || {
+            extrapolate_parallel(lines.into_iter().map(Ok), workers, Mode::Pyramid, false)
+        });
+        assert!(cfg!(not(debug_assertions)) || result.is_err());
+    }
+}
+
+#[test]
+#[ignore = "benchmark, run with `cargo test --release -- --ignored --nocapture`"]
+fn bench_modes() {
+    let n: i64 = 300_000;
+    let line = (0..n)
+        .map(|x| (2 * x * x * x - 7 * x * x + 3).to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let expected = 2 * n * n * n - 7 * n * n + 3;
+
+    for mode in [Mode::Pyramid, Mode::Streaming, Mode::Binomial] {
+        let start = std::time::Instant::now();
+        let result = extrapolate(&line, mode, false).unwrap();
+        println!("{mode:?}: {:?}", start.elapsed());
+        assert_eq!(result, expected);
+    }
+
+    // Without a low degree the pyramid needs O(n^2) memory.
+    let line = (0..3_000)
+        .map(|x: i64| (x % 7 - 3).to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    for mode in [Mode::Pyramid, Mode::Streaming, Mode::Binomial] {
+        let start = std::time::Instant::now();
+        let result = std::panic::catch_unwind(|| extrapolate(&line, mode, false).unwrap());
+        println!("{mode:?} (degree n - 1): {:?} {result:?}", start.elapsed());
+    }
+}
@@ -0,0 +1,207 @@
+use std::{collections::HashSet, fmt::Display};
+
+use anyhow::{bail, Error};
+
+use crate::{scan, step, Heading, Pipe, Tile};
+
+/// Closed pipe cycle.
+#[derive(Debug)]
+pub struct Cycle {
+    /// Tiles in walk order.
+    pub tiles: Vec<(usize, usize)>,
+    /// Number of tiles enclosed by the cycle.
+    pub enclosed: usize,
+}
+
+/// Chain of connected pipes that ends in a dead end on both sides.
+#[derive(Debug)]
+pub struct Chain {
+    /// Tiles in walk order, from one dead end to the other.
+    pub tiles: Vec<(usize, usize)>,
+}
+
+/// Every cycle and dead-end chain in a pipe grid.
+#[derive(Debug)]
+pub struct Analysis {
+    pub cycles: Vec<Cycle>,
+    pub chains: Vec<Chain>,
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Loops: {}", self.cycles.len())?;
+        for cycle in &self.cycles {
+            writeln!(
+                f,
+                "  length {}, enclosed {}, through {:?}",
+                cycle.tiles.len(),
+                cycle.enclosed,
+                cycle.tiles[0]
+            )?;
+        }
+
+        writeln!(f, "Dead-end chains: {}", self.chains.len())?;
+        for chain in &self.chains {
+            writeln!(
+                f,
+                "  length {}, from {:?} to {:?}",
+                chain.tiles.len(),
+                chain.tiles[0],
+                chain.tiles[chain.tiles.len() - 1]
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Follows the pipes from `from` towards `heading` until a dead end or the
+/// walk is about to leave `from` towards `heading` again. Returns the tiles
+/// after `from` and whether the walk closed a cycle. Every opening used on
+/// the way is marked in `seen`.
+///
+/// Crossings are passed straight through, so a walk can pass a crossing
+/// twice, once in each direction.
+fn follow(
+    pipes: &[Vec<Pipe>],
+    from: (usize, usize),
+    heading: Heading,
+    seen: &mut HashSet<((usize, usize), Heading)>,
+) -> (Vec<(usize, usize)>, bool) {
+    let mut tiles = Vec::new();
+    let mut current = from;
+    let mut exit = heading;
+
+    loop {
+        seen.insert((current, exit));
+        let Some(next) = step(pipes, current, exit, false) else {
+            return (tiles, false);
+        };
+        let Ok(next_exit) = pipes[next.0][next.1].next_heading(exit) else {
+            return (tiles, false);
+        };
+        seen.insert((next, exit.opposite()));
+
+        if (next, next_exit) == (from, heading) {
+            return (tiles, true);
+        }
+        tiles.push(next);
+        (current, exit) = (next, next_exit);
+    }
+}
+
+/// Splits all pipes of the grid into closed cycles and dead-end chains.
+///
+/// Straight pipes and bends connect to at most two neighbors, and a crossing
+/// is two straight pipes on one tile, so each group of connected pipes is
+/// either a cycle or a chain. Junctions branch, which leaves no such split,
+/// so grids with them are rejected.
+pub fn analyze(pipes: &[Vec<Pipe>]) -> Result<Analysis, Error> {
+    for (row, line) in pipes.iter().enumerate() {
+        for (col, pipe) in line.iter().enumerate() {
+            if pipe.headings().len() == 3 {
+                bail!(
+                    "cannot split the grid into cycles and chains, {pipe:?} at {:?} branches",
+                    (row, col)
+                );
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut cycles = Vec::new();
+    let mut chains = Vec::new();
+
+    for row in 0..pipes.len() {
+        for col in 0..pipes[row].len() {
+            let tile = (row, col);
+            let pipe = pipes[row][col];
+
+            for &heading in pipe.headings() {
+                if seen.contains(&(tile, heading)) {
+                    continue;
+                }
+
+                let (forward, closed) = follow(pipes, tile, heading, &mut seen);
+                if closed {
+                    let mut tiles = vec![tile];
+                    tiles.extend(forward);
+                    let enclosed = scan(pipes, &tiles)
+                        .iter()
+                        .flatten()
+                        .filter(|tile| **tile == Tile::Inside)
+                        .count();
+                    cycles.push(Cycle { tiles, enclosed });
+                    continue;
+                }
+
+                // The other end of the pass through `tile` that `heading`
+                // belongs to.
+                let back = pipe.next_heading(heading.opposite())?;
+                let mut tiles = follow(pipes, tile, back, &mut seen).0;
+                tiles.reverse();
+                tiles.push(tile);
+                tiles.extend(forward);
+                chains.push(Chain { tiles });
+            }
+        }
+    }
+
+    Ok(Analysis { cycles, chains })
+}
+
+#[test]
+fn test_analyze() {
+    let builder = crate::builder_from([
+        "F-7.F--7", "|.|.|F7|", "L-J.||LJ", "..-.LJ..", "F-7.....", "L-|.F7-.",
+    ]);
+    let analysis = analyze(&builder.pipes).unwrap();
+
+    let cycles: Vec<(usize, usize)> = analysis
+        .cycles
+        .iter()
+        .map(|cycle| (cycle.tiles.len(), cycle.enclosed))
+        .collect();
+    assert_eq!(cycles, vec![(8, 1), (14, 0)]);
+
+    let chains: Vec<Vec<(usize, usize)>> = analysis
+        .chains
+        .iter()
+        .map(|chain| chain.tiles.clone())
+        .collect();
+    assert_eq!(
+        chains,
+        vec![
+            vec![(3, 2)],
+            vec![(5, 1), (5, 0), (4, 0), (4, 1), (4, 2), (5, 2)],
+            vec![(5, 4), (5, 5)],
+            vec![(5, 6)],
+        ]
+    );
+
+    // A figure eight passes its crossing twice, a crossing of two chains
+    // belongs to both.
+    let builder = crate::builder_from(["F-7..|.", "|.|.-+-", "L-+-7|.", "..|.|..", "..L-J.."]);
+    let analysis = analyze(&builder.pipes).unwrap();
+    let cycles: Vec<(usize, usize)> = analysis
+        .cycles
+        .iter()
+        .map(|cycle| (cycle.tiles.len(), cycle.enclosed))
+        .collect();
+    assert_eq!(cycles, vec![(16, 2)]);
+    let chains: Vec<Vec<(usize, usize)>> = analysis
+        .chains
+        .iter()
+        .map(|chain| chain.tiles.clone())
+        .collect();
+    assert_eq!(
+        chains,
+        vec![vec![(0, 5), (1, 5), (2, 5)], vec![(1, 4), (1, 5), (1, 6)]]
+    );
+
+    let builder = crate::builder_from(["..F-7", "..|.|", "..├-J", "..|..", "..L-.", "....."]);
+    assert_eq!(
+        analyze(&builder.pipes).unwrap_err().to_string(),
+        "cannot split the grid into cycles and chains, TeeEast at (2, 2) branches"
+    );
+}
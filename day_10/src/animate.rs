@@ -0,0 +1,120 @@
+use std::{collections::HashSet, io::Write, path::Path, thread, time::Duration};
+
+use anyhow::{Context, Error};
+
+use crate::{Solution, Solver};
+
+const CLEAR: &str = "\x1b[H\x1b[2J";
+const RESET: &str = "\x1b[0m";
+const WALKED: &str = "\x1b[1;33m";
+const HEAD: &str = "\x1b[1;30;43m";
+const AHEAD: &str = "\x1b[2m";
+
+impl Solver {
+    /// Draws the walk along the loop after `steps` steps from the start,
+    /// below a line with the step count. The tiles walked so far are drawn
+    /// with box-drawing characters and the current tile is marked `@`.
+    ///
+    /// With `ansi` the current tile keeps its glyph and is highlighted
+    /// instead, and the pipes not walked yet are drawn dimmed.
+    pub fn frame(&self, solution: &Solution, steps: usize, ansi: bool) -> String {
+        let tiles = &solution.loop_tiles;
+        let walked: HashSet<(usize, usize)> = tiles[..(steps + 1).min(tiles.len())]
+            .iter()
+            .copied()
+            .collect();
+        let head = tiles[steps % tiles.len()];
+
+        let mut out = format!("Step {steps}/{}\n", tiles.len());
+
+        for (row, pipes) in self.pipes.iter().enumerate() {
+            for (col, pipe) in pipes.iter().enumerate() {
+                let (color, ch) = if (row, col) == head {
+                    (HEAD, if ansi { pipe.box_drawing() } else { '@' })
+                } else if walked.contains(&(row, col)) {
+                    (WALKED, pipe.box_drawing())
+                } else if ansi {
+                    (AHEAD, pipe.box_drawing())
+                } else {
+                    ("", '.')
+                };
+
+                if ansi {
+                    out.push_str(color);
+                    out.push(ch);
+                    out.push_str(RESET);
+                } else {
+                    out.push(ch);
+                }
+            }
+
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Plays the walk along the loop in the terminal, redrawing the grid
+    /// every `delay` until the walk is back at the start.
+    pub fn animate(
+        &self,
+        solution: &Solution,
+        delay: Duration,
+        out: &mut impl Write,
+    ) -> Result<(), Error> {
+        for steps in 0..=solution.loop_length {
+            write!(out, "{CLEAR}{}", self.frame(solution, steps, true))?;
+            out.flush()?;
+            thread::sleep(delay);
+        }
+
+        Ok(())
+    }
+
+    /// Writes every frame of the walk as plain text to `frame_NNNN.txt`
+    /// files in `dir`, creating it if needed. Returns the number of frames.
+    pub fn write_frames(&self, solution: &Solution, dir: &Path) -> Result<usize, Error> {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+
+        for steps in 0..=solution.loop_length {
+            let path = dir.join(format!("frame_{steps:04}.txt"));
+            std::fs::write(&path, self.frame(solution, steps, false))
+                .with_context(|| format!("writing {}", path.display()))?;
+        }
+
+        Ok(solution.loop_length + 1)
+    }
+}
+
+#[test]
+fn test_frames() {
+    let (solver, solution) = crate::solve_lines([".....", ".S-7.", ".|.|.", ".L-J.", "....."]);
+
+    assert_eq!(
+        solver.frame(&solution, 0, false),
+        "Step 0/8\n.....\n.@...\n.....\n.....\n.....\n"
+    );
+    assert_eq!(
+        solver.frame(&solution, 3, false),
+        "Step 3/8\n.....\n.┌─┐.\n...@.\n.....\n.....\n"
+    );
+    assert_eq!(
+        solver.frame(&solution, 8, false),
+        "Step 8/8\n.....\n.@─┐.\n.│.│.\n.└─┘.\n.....\n"
+    );
+    assert!(solver
+        .frame(&solution, 3, true)
+        .contains(&format!("{HEAD}│{RESET}")));
+
+    let dir = std::env::temp_dir().join(format!("day_10_frames_{}", std::process::id()));
+    assert_eq!(solver.write_frames(&solution, &dir).unwrap(), 9);
+    assert_eq!(
+        std::fs::read_to_string(dir.join("frame_0003.txt")).unwrap(),
+        solver.frame(&solution, 3, false)
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+
+    let mut out = Vec::new();
+    solver.animate(&solution, Duration::ZERO, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap().matches(CLEAR).count(), 9);
+}
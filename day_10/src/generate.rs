@@ -0,0 +1,331 @@
+use std::fs;
+
+use anyhow::{bail, Context, Error};
+
+use crate::{interior_points, Heading, Pipe};
+
+/// Small xorshift generator, good enough for mazes and reproducible from a
+/// seed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero.
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Random number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Settings for a random pipe maze with one loop through the start.
+pub struct Generator {
+    pub rows: usize,
+    pub cols: usize,
+    /// Length the loop is grown to, must be even.
+    pub length: usize,
+    /// Share of the tiles off the loop that get a random junk pipe.
+    pub junk: f64,
+    pub seed: u64,
+}
+
+/// Generated maze together with the answers `Solver::solve` should find.
+pub struct Maze {
+    pub lines: Vec<String>,
+    pub loop_length: usize,
+    pub farthest_distance: usize,
+    pub enclosed: usize,
+}
+
+const NONE: usize = usize::MAX;
+
+impl Generator {
+    /// Starts with a clockwise loop around a single point and grows it by
+    /// random moves until it reaches the target length:
+    ///
+    /// - a bump moves both tiles of a loop edge sideways by one tile, which
+    ///   makes the loop two tiles longer, but encloses nothing new,
+    /// - a flip cuts a concave corner, so that the corner tile ends up
+    ///   inside the loop.
+    pub fn generate(&self) -> Result<Maze, Error> {
+        let (rows, cols) = (self.rows, self.cols);
+
+        if rows < 2 || cols < 2 {
+            bail!("grid must be at least 2x2");
+        }
+        if self.length < 4 || !self.length.is_multiple_of(2) || self.length > rows * cols {
+            bail!("loop length must be even, at least 4 and fit into the grid");
+        }
+
+        let mut rng = Rng::new(self.seed);
+        let mut lp = Loop::new(rows, cols);
+
+        let row = rng.below(rows - 1);
+        let col = rng.below(cols - 1);
+        let square = [
+            (row, col),
+            (row, col + 1),
+            (row + 1, col + 1),
+            (row + 1, col),
+        ];
+        for i in 0..4 {
+            lp.link(lp.id(square[i]), lp.id(square[(i + 1) % 4]));
+            lp.add(lp.id(square[i]));
+        }
+
+        let mut attempts = 0;
+        while lp.tiles.len() < self.length {
+            attempts += 1;
+            if attempts > 1000 * self.length {
+                bail!(
+                    "could not grow the loop to length {}, reached {}",
+                    self.length,
+                    lp.tiles.len()
+                );
+            }
+
+            let tile = lp.tiles[rng.below(lp.tiles.len())];
+            if rng.below(2) == 0 {
+                lp.bump(tile, rng.below(2) == 0);
+            } else {
+                lp.flip(tile);
+            }
+        }
+
+        for _ in 0..self.length {
+            let tile = lp.tiles[rng.below(lp.tiles.len())];
+            lp.flip(tile);
+        }
+
+        let Loop {
+            next, prev, tiles, ..
+        } = lp;
+
+        let start = tiles[rng.below(tiles.len())];
+        let mut order = vec![start];
+        let mut tile = next[start];
+        while tile != start {
+            order.push(tile);
+            tile = next[tile];
+        }
+
+        let heading = |from: usize, to: usize| match to as i64 - from as i64 {
+            diff if diff == -(cols as i64) => Heading::North,
+            diff if diff == cols as i64 => Heading::South,
+            1 => Heading::East,
+            _ => Heading::West,
+        };
+
+        let mut grid = vec![vec!['.'; cols]; rows];
+        for &tile in &order {
+            let pipe = Pipe::try_from(&[heading(tile, next[tile]), heading(tile, prev[tile])])?;
+            grid[tile / cols][tile % cols] = pipe.ascii();
+        }
+
+        const JUNK: [Pipe; 6] = [
+            Pipe::Horizontal,
+            Pipe::Vertical,
+            Pipe::BendNorthEast,
+            Pipe::BendNorthWest,
+            Pipe::BendSouthWest,
+            Pipe::BendSouthEast,
+        ];
+        let (start_row, start_col) = (start / cols, start % cols);
+        for (row, line) in grid.iter_mut().enumerate() {
+            for (col, ch) in line.iter_mut().enumerate() {
+                if *ch != '.' || rng.below(1_000_000) as f64 >= self.junk * 1_000_000.0 {
+                    continue;
+                }
+
+                // Junk next to the start must not connect to it, otherwise
+                // the start pipe could become ambiguous.
+                let pipe = JUNK[rng.below(JUNK.len())];
+                let points_at_start = pipe.headings().iter().any(|heading| {
+                    let offset = heading.to_offsets();
+                    row as i64 + offset.0 == start_row as i64
+                        && col as i64 + offset.1 == start_col as i64
+                });
+                if !points_at_start {
+                    *ch = pipe.ascii();
+                }
+            }
+        }
+        grid[start_row][start_col] = 'S';
+
+        let positions: Vec<(usize, usize)> = order
+            .iter()
+            .map(|tile| (tile / cols, tile % cols))
+            .collect();
+
+        Ok(Maze {
+            lines: grid.into_iter().map(String::from_iter).collect(),
+            loop_length: order.len(),
+            farthest_distance: order.len() / 2,
+            enclosed: interior_points(&positions, order.len()),
+        })
+    }
+}
+
+/// Loop as a doubly linked list over tile ids, so that moves take constant
+/// time.
+struct Loop {
+    rows: usize,
+    cols: usize,
+    next: Vec<usize>,
+    prev: Vec<usize>,
+    /// Every tile of the loop, `index[tile]` is its position in here.
+    tiles: Vec<usize>,
+    index: Vec<usize>,
+}
+
+impl Loop {
+    fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            next: vec![NONE; rows * cols],
+            prev: vec![NONE; rows * cols],
+            tiles: Vec::new(),
+            index: vec![NONE; rows * cols],
+        }
+    }
+
+    fn id(&self, (row, col): (usize, usize)) -> usize {
+        row * self.cols + col
+    }
+
+    fn link(&mut self, from: usize, to: usize) {
+        self.next[from] = to;
+        self.prev[to] = from;
+    }
+
+    fn add(&mut self, tile: usize) {
+        self.index[tile] = self.tiles.len();
+        self.tiles.push(tile);
+    }
+
+    /// Tile at `tile + offset`, if it is within the grid and not on the
+    /// loop yet.
+    fn free(&self, tile: usize, offset: (i64, i64)) -> Option<usize> {
+        let row = (tile / self.cols) as i64 + offset.0;
+        let col = (tile % self.cols) as i64 + offset.1;
+
+        if row < 0 || row >= self.rows as i64 || col < 0 || col >= self.cols as i64 {
+            return None;
+        }
+
+        let id = row as usize * self.cols + col as usize;
+        (self.next[id] == NONE).then_some(id)
+    }
+
+    fn offset(&self, from: usize, to: usize) -> (i64, i64) {
+        (
+            (to / self.cols) as i64 - (from / self.cols) as i64,
+            (to % self.cols) as i64 - (from % self.cols) as i64,
+        )
+    }
+
+    /// Moves the edge from `tile` to its successor sideways, to the left
+    /// (outwards, as the loop runs clockwise) or to the right.
+    fn bump(&mut self, tile: usize, outwards: bool) {
+        let next = self.next[tile];
+        let (d_row, d_col) = self.offset(tile, next);
+        let side = if outwards {
+            (-d_col, d_row)
+        } else {
+            (d_col, -d_row)
+        };
+
+        let (Some(a), Some(b)) = (self.free(tile, side), self.free(next, side)) else {
+            return;
+        };
+
+        self.link(tile, a);
+        self.link(a, b);
+        self.link(b, next);
+        self.add(a);
+        self.add(b);
+    }
+
+    /// Replaces `tile` by the fourth corner of the square it spans with its
+    /// neighbors, if it is a concave corner.
+    fn flip(&mut self, tile: usize) {
+        let prev = self.prev[tile];
+        let next = self.next[tile];
+        let incoming = self.offset(prev, tile);
+        let outgoing = self.offset(tile, next);
+
+        // Left turns are concave, as the loop runs clockwise.
+        if incoming.1 * outgoing.0 - incoming.0 * outgoing.1 != -1 {
+            return;
+        }
+
+        let Some(corner) = self.free(tile, (outgoing.0 - incoming.0, outgoing.1 - incoming.1))
+        else {
+            return;
+        };
+
+        self.link(prev, corner);
+        self.link(corner, next);
+        self.next[tile] = NONE;
+        self.prev[tile] = NONE;
+
+        let index = self.index[tile];
+        self.tiles[index] = corner;
+        self.index[corner] = index;
+    }
+}
+
+/// Command line entry point:
+/// `generate ROWSxCOLS LENGTH [--seed N] [--junk SHARE] [--output PATH]`.
+///
+/// With `--output`, the expected answers are written to `PATH.answers`.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let size = args.next().context("grid size missing")?;
+    let (rows, cols) = size.split_once('x').context("size must be ROWSxCOLS")?;
+    let length = args.next().context("loop length missing")?.parse()?;
+
+    let mut generator = Generator {
+        rows: rows.parse()?,
+        cols: cols.parse()?,
+        length,
+        junk: 0.3,
+        seed: 0,
+    };
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => generator.seed = args.next().context("missing seed")?.parse()?,
+            "--junk" => generator.junk = args.next().context("missing share")?.parse()?,
+            "--output" => output = Some(args.next().context("missing path")?),
+            _ => bail!("unknown argument {arg}"),
+        }
+    }
+
+    let maze = generator.generate()?;
+    let text = maze.lines.join("\n") + "\n";
+    let answers = format!(
+        "Part 1: {}\nPart 2: {}\nLoop length: {}\n",
+        maze.farthest_distance, maze.enclosed, maze.loop_length
+    );
+
+    match output {
+        Some(path) => {
+            fs::write(&path, text)?;
+            fs::write(format!("{path}.answers"), answers)?;
+        }
+        None => print!("{text}"),
+    }
+
+    Ok(())
+}
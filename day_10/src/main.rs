@@ -1,9 +1,20 @@
 use std::collections::HashSet;
 
-use anyhow::{anyhow, bail, Context, Error};
+use anyhow::{bail, Context, Error};
 use common_rust::read_lines;
 
-#[derive(Debug, Clone, Copy)]
+mod analysis;
+mod animate;
+mod generate;
+mod orientation;
+mod path;
+mod pockets;
+mod render;
+mod repair;
+mod svg;
+mod wrap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Pipe {
     Horizontal,
     Vertical,
@@ -11,39 +22,48 @@ enum Pipe {
     BendSouthEast,
     BendNorthWest,
     BendNorthEast,
-    NoPipe,
+    /// Lets a path pass straight through both ways.
+    Crossing,
+    /// T-junctions, named by the heading of their stem.
+    TeeNorth,
+    TeeEast,
+    TeeSouth,
+    TeeWest,
+    Ground,
 }
 
 impl Pipe {
+    /// Heading a walk leaves the pipe with after entering it with `heading`.
+    /// Where the pipe branches, the walk goes straight if it can and turns
+    /// right otherwise.
     fn next_heading(self, heading: Heading) -> Result<Heading, Error> {
-        match (heading, self) {
-            (Heading::North, Pipe::Vertical) => Ok(Heading::North),
-            (Heading::North, Pipe::BendSouthWest) => Ok(Heading::West),
-            (Heading::North, Pipe::BendSouthEast) => Ok(Heading::East),
-            (Heading::South, Pipe::Vertical) => Ok(Heading::South),
-            (Heading::South, Pipe::BendNorthWest) => Ok(Heading::West),
-            (Heading::South, Pipe::BendNorthEast) => Ok(Heading::East),
-            (Heading::East, Pipe::Horizontal) => Ok(Heading::East),
-            (Heading::East, Pipe::BendSouthWest) => Ok(Heading::South),
-            (Heading::East, Pipe::BendNorthWest) => Ok(Heading::North),
-            (Heading::West, Pipe::Horizontal) => Ok(Heading::West),
-            (Heading::West, Pipe::BendSouthEast) => Ok(Heading::South),
-            (Heading::West, Pipe::BendNorthEast) => Ok(Heading::North),
-            (_, _) => Err(anyhow!("no path")),
+        let headings = self.headings();
+        if !headings.contains(&heading.opposite()) {
+            bail!("no path");
         }
+
+        [heading, heading.right(), heading.left()]
+            .into_iter()
+            .find(|exit| headings.contains(exit))
+            .context("no path")
     }
 
-    fn headings(self) -> [Heading; 2] {
+    fn headings(self) -> &'static [Heading] {
         use Heading::*;
 
         match self {
-            Pipe::Horizontal => [East, West],
-            Pipe::Vertical => [South, North],
-            Pipe::BendSouthWest => [West, South],
-            Pipe::BendSouthEast => [East, South],
-            Pipe::BendNorthWest => [West, North],
-            Pipe::BendNorthEast => [North, East],
-            Pipe::NoPipe => panic!("invalid"),
+            Pipe::Horizontal => &[East, West],
+            Pipe::Vertical => &[South, North],
+            Pipe::BendSouthWest => &[West, South],
+            Pipe::BendSouthEast => &[East, South],
+            Pipe::BendNorthWest => &[West, North],
+            Pipe::BendNorthEast => &[North, East],
+            Pipe::Crossing => &[North, East, South, West],
+            Pipe::TeeNorth => &[North, East, West],
+            Pipe::TeeEast => &[North, East, South],
+            Pipe::TeeSouth => &[East, South, West],
+            Pipe::TeeWest => &[North, South, West],
+            Pipe::Ground => &[],
         }
     }
 }
@@ -72,7 +92,7 @@ impl TryFrom<&[Heading; 2]> for Pipe {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Heading {
     North,
     East,
@@ -89,6 +109,106 @@ impl Heading {
             Heading::West => (0, -1),
         }
     }
+
+    fn opposite(self) -> Self {
+        match self {
+            Heading::North => Heading::South,
+            Heading::East => Heading::West,
+            Heading::South => Heading::North,
+            Heading::West => Heading::East,
+        }
+    }
+
+    fn right(self) -> Self {
+        match self {
+            Heading::North => Heading::East,
+            Heading::East => Heading::South,
+            Heading::South => Heading::West,
+            Heading::West => Heading::North,
+        }
+    }
+
+    fn left(self) -> Self {
+        self.right().opposite()
+    }
+}
+
+/// Neighbor of `tile` towards `heading`, if it lies within the grid. With
+/// `wrap` set, steps leaving the grid come back on the opposite edge.
+fn step(
+    pipes: &[Vec<Pipe>],
+    tile: (usize, usize),
+    heading: Heading,
+    wrap: bool,
+) -> Option<(usize, usize)> {
+    let offset = heading.to_offsets();
+    let mut row = tile.0 as i64 + offset.0;
+    let mut col = tile.1 as i64 + offset.1;
+
+    if wrap {
+        row = row.rem_euclid(pipes.len() as i64);
+        col = col.rem_euclid(pipes[row as usize].len() as i64);
+    }
+
+    if row < 0
+        || row as usize >= pipes.len()
+        || col < 0
+        || col as usize >= pipes[row as usize].len()
+    {
+        return None;
+    }
+
+    Some((row as usize, col as usize))
+}
+
+/// Follows the pipes from `start` towards `heading` until the walk is back
+/// at `start`. Returns the tiles in walk order, beginning with `start`, and
+/// the heading the walk arrives with.
+///
+/// Every step is checked against the grid, so a loop that leaves it or runs
+/// into a pipe it cannot enter results in an error instead of a panic. A
+/// walk that junctions lead into a cycle away from `start` is an error too.
+fn walk(
+    pipes: &[Vec<Pipe>],
+    start: (usize, usize),
+    mut heading: Heading,
+    wrap: bool,
+) -> Result<(Vec<(usize, usize)>, Heading), Error> {
+    let (mut row, mut col) = start;
+    let mut tiles = Vec::new();
+    let mut seen = HashSet::new();
+
+    loop {
+        tiles.push((row, col));
+
+        let Some(next) = step(pipes, (row, col), heading, wrap) else {
+            bail!(
+                "walk leaves the grid at {:?} heading {heading:?} from {:?}",
+                (row, col),
+                pipes[row][col]
+            );
+        };
+
+        (row, col) = next;
+        if (row, col) == start {
+            return Ok((tiles, heading));
+        }
+
+        let pipe = pipes[row][col];
+        heading = pipe.next_heading(heading).with_context(|| {
+            format!(
+                "walk breaks at {:?} heading {heading:?} into {pipe:?}",
+                (row, col)
+            )
+        })?;
+
+        if !seen.insert(((row, col), heading)) {
+            bail!(
+                "walk leaves {:?} heading {heading:?} again, without returning to the start",
+                (row, col)
+            );
+        }
+    }
 }
 
 impl TryFrom<char> for Pipe {
@@ -102,16 +222,24 @@ impl TryFrom<char> for Pipe {
             'J' => Ok(Self::BendNorthWest),
             '7' => Ok(Self::BendSouthWest),
             'F' => Ok(Self::BendSouthEast),
-            'S' => Ok(Self::NoPipe),
-            '.' => Ok(Self::NoPipe),
+            '+' | '┼' => Ok(Self::Crossing),
+            '┴' => Ok(Self::TeeNorth),
+            '├' => Ok(Self::TeeEast),
+            '┬' => Ok(Self::TeeSouth),
+            '┤' => Ok(Self::TeeWest),
+            'S' => Ok(Self::Ground),
+            '.' => Ok(Self::Ground),
             _ => Err(anyhow::anyhow!("invalid pipe")),
         }
     }
 }
 
+#[derive(Debug, Clone)]
 struct SolverBuilder {
     pipes: Vec<Vec<Pipe>>,
     start: Option<(usize, usize)>,
+    /// Whether the grid wraps around at its edges, like a torus.
+    wrap: bool,
 }
 
 impl SolverBuilder {
@@ -119,6 +247,7 @@ impl SolverBuilder {
         SolverBuilder {
             pipes: Vec::new(),
             start: None,
+            wrap: false,
         }
     }
 
@@ -148,118 +277,671 @@ impl SolverBuilder {
 
         let start = self.start.context("no start")?;
 
-        let mut connected_dirs = [North, North];
-        let mut index = 0;
+        // Ragged rows are padded with ground, so that every row can be
+        // indexed up to the full width.
+        let width = self.pipes.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut self.pipes {
+            row.resize(width, Pipe::Ground);
+        }
 
-        for heading in [North, East, South, West] {
-            let offset = heading.to_offsets();
+        let mut connected = Vec::new();
 
-            let neighbor_row = start.0 as i64 + offset.0;
-            let neighbor_col = start.1 as i64 + offset.1;
+        // On narrower grids, a wrapping step east and one west would reach
+        // the same tile, and the walk could not tell them apart.
+        if self.wrap && (self.pipes.len() < 3 || width < 3) {
+            bail!("a wrapping grid needs at least 3 rows and 3 columns");
+        }
 
-            if neighbor_row < 0 || neighbor_row as usize >= self.pipes.len() {
-                continue;
-            }
-            if neighbor_col < 0 || neighbor_col as usize >= self.pipes[neighbor_row as usize].len()
-            {
+        for heading in [North, East, South, West] {
+            let Some((row, col)) = step(&self.pipes, start, heading, self.wrap) else {
                 continue;
-            }
+            };
 
-            let neighbor = self.pipes[neighbor_row as usize][neighbor_col as usize];
-
-            if neighbor.next_heading(heading).is_ok() {
-                if index > 1 {
-                    bail!("invalid start");
-                }
-                connected_dirs[index] = heading;
-                index += 1;
+            if self.pipes[row][col].next_heading(heading).is_ok() {
+                connected.push(heading);
             }
         }
 
+        let connected_dirs = if connected.len() < 2 {
+            bail!("start connects to {} neighbors", connected.len());
+        } else if connected.len() == 2 {
+            [connected[0], connected[1]]
+        } else {
+            self.disambiguate_start(start, &connected)?
+        };
+
         let start_pipe = (&connected_dirs).try_into()?;
         self.pipes[start.0][start.1] = start_pipe;
 
         Ok(Solver {
             pipes: self.pipes,
             start,
+            wrap: self.wrap,
         })
     }
+
+    /// Tries every pair of headings connected to the start and keeps the
+    /// first one that forms a closed loop back to the start.
+    fn disambiguate_start(
+        &self,
+        start: (usize, usize),
+        connected: &[Heading],
+    ) -> Result<[Heading; 2], Error> {
+        let mut rejected = Vec::new();
+
+        for (i, &first) in connected.iter().enumerate() {
+            for &second in &connected[i + 1..] {
+                match walk(&self.pipes, start, first, self.wrap) {
+                    Ok((_, arrival)) if arrival.opposite() == second => return Ok([first, second]),
+                    Ok((_, arrival)) => rejected.push(format!(
+                        "{first:?}/{second:?}: loop returns from {:?}",
+                        arrival.opposite()
+                    )),
+                    Err(e) => rejected.push(format!("{first:?}/{second:?}: {e:#}")),
+                }
+            }
+        }
+
+        bail!(
+            "invalid start, no pair of headings forms a loop\n{}",
+            rejected.join("\n")
+        )
+    }
+}
+
+/// Where a tile lies relative to the loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tile {
+    Loop,
+    Inside,
+    Outside,
+}
+
+/// Everything `Solver::solve` finds out about the loop through the start.
+#[derive(Debug)]
+struct Solution {
+    /// Number of tiles in the loop.
+    loop_length: usize,
+    /// Number of steps along the loop to the tile farthest from the start.
+    farthest_distance: usize,
+    /// Number of tiles enclosed by the loop.
+    enclosed: usize,
+    /// Tiles of the loop in walk order, beginning with the start.
+    loop_tiles: Vec<(usize, usize)>,
+    /// Pipe hidden under the start tile.
+    start_pipe: Pipe,
+    /// Position of every tile relative to the loop.
+    tiles: Vec<Vec<Tile>>,
 }
 
 #[derive(Debug)]
 struct Solver {
     pipes: Vec<Vec<Pipe>>,
     start: (usize, usize),
+    wrap: bool,
 }
 
 impl Solver {
-    fn solve(&self) -> Result<i32, Error> {
+    fn solve(&self) -> Result<Solution, Error> {
         let start_pipe = self.pipes[self.start.0][self.start.1];
-        let mut visited = HashSet::new();
+        let heading = start_pipe.headings()[0];
+
+        let (loop_tiles, _) = walk(&self.pipes, self.start, heading, self.wrap)?;
+        let steps = loop_tiles.len();
+
+        let tiles = if self.wrap {
+            self.scan_wrapping(&loop_tiles)?
+        } else {
+            scan(&self.pipes, &loop_tiles)
+        };
+        let fields = tiles
+            .iter()
+            .flatten()
+            .filter(|tile| **tile == Tile::Inside)
+            .count();
+
+        Ok(Solution {
+            loop_length: steps,
+            farthest_distance: steps / 2,
+            enclosed: fields,
+            loop_tiles,
+            start_pipe,
+            tiles,
+        })
+    }
 
-        let mut heading = start_pipe.headings()[0];
+    /// Corners of the loop in walk order, beginning with the start if it is
+    /// one.
+    fn vertices(&self, solution: &Solution) -> Vec<(usize, usize)> {
+        let tiles = &solution.loop_tiles;
+        let n = tiles.len();
+
+        (0..n)
+            .filter(|&i| {
+                let incoming = Heading::between(tiles[(i + n - 1) % n], tiles[i]);
+                incoming != Heading::between(tiles[i], tiles[(i + 1) % n])
+            })
+            .map(|i| tiles[i])
+            .collect()
+    }
 
-        let mut row = self.start.0;
-        let mut col = self.start.1;
-        let mut steps = 1;
+    /// Counts the enclosed tiles from the area of the loop polygon (shoelace
+    /// formula) and Pick's theorem, as a cross-check of the parity scan.
+    /// This only holds for loops that do not cross themselves.
+    fn enclosed_by_area(&self, solution: &Solution) -> usize {
+        interior_points(&self.vertices(solution), solution.loop_length)
+    }
 
-        loop {
-            let offset = heading.to_offsets();
-            visited.insert((row, col));
+    /// Distance of every loop tile from the start, in steps along the loop
+    /// in whichever direction is shorter. Only the loop's own steps count,
+    /// so the spare openings of junctions and crossings lead nowhere, and a
+    /// crossing passed twice keeps the smaller of its two distances.
+    fn distances(&self, solution: &Solution) -> Vec<Vec<Option<usize>>> {
+        let mut distances: Vec<Vec<Option<usize>>> =
+            self.pipes.iter().map(|row| vec![None; row.len()]).collect();
+        let n = solution.loop_tiles.len();
+
+        for (i, (row, col)) in solution.loop_tiles.iter().enumerate() {
+            let distance = i.min(n - i);
+            let tile = &mut distances[*row][*col];
+            *tile = Some(tile.map_or(distance, |other| other.min(distance)));
+        }
 
-            row = (row as i64 + offset.0) as usize;
-            col = (col as i64 + offset.1) as usize;
-            if col == self.start.1 && row == self.start.0 {
-                break;
-            }
+        distances
+    }
+}
 
-            let pipe = self.pipes[row][col];
-            heading = pipe.next_heading(heading)?;
+fn main() -> Result<(), Error> {
+    let mut args = std::env::args().skip(1);
+    let a = args.next().context("Input file missing")?;
 
-            steps += 1;
+    if a == "generate" {
+        return generate::run(args);
+    }
+    if a == "decode" {
+        let encoded = args.next().context("path missing")?;
+        for line in path::decode_path(&encoded)? {
+            println!("{line}");
         }
+        return Ok(());
+    }
 
-        let mut fields = 0;
-
-        for row in 0..self.pipes.len() {
-            let mut inside = false;
-            for col in 0..self.pipes[row].len() {
-                if visited.contains(&(row, col)) {
-                    let pipe = self.pipes[row][col];
-
-                    match &pipe {
-                        Pipe::BendNorthWest | Pipe::Vertical | Pipe::BendNorthEast => {
-                            inside = !inside;
-                        }
-                        _ => {}
-                    }
-                } else if inside {
-                    fields += 1;
-                }
+    let mut print_tiles = false;
+    let mut check = false;
+    let mut style = None;
+    let mut print_distances = false;
+    let mut all_loops = false;
+    let mut svg = None;
+    let mut points = Vec::new();
+    let mut encode = false;
+    let mut max_repair = None;
+    let mut wrap = false;
+    let mut animate = None;
+    let mut frames = None;
+    let mut pockets = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tiles" => print_tiles = true,
+            "--check" => check = true,
+            "--distances" => print_distances = true,
+            "--all-loops" => all_loops = true,
+            "--encode" => encode = true,
+            "--pockets" => pockets = true,
+            "--wrap" => wrap = true,
+            "--animate" => {
+                let delay = args.next().context("missing frame delay in ms")?;
+                animate = Some(std::time::Duration::from_millis(delay.parse()?));
+            }
+            "--frames" => frames = Some(args.next().context("missing frames directory")?),
+            "--repair" => max_repair = Some(args.next().context("missing maximum cost")?.parse()?),
+            "--point" => {
+                let point = args.next().context("missing point")?;
+                let (row, col) = point.split_once(',').context("point must be ROW,COL")?;
+                points.push((row.parse::<usize>()?, col.parse::<usize>()?));
             }
+            "--svg" => svg = Some(args.next().context("missing svg path")?),
+            "--render" => style = Some(args.next().context("missing style")?.parse()?),
+            _ => bail!("unknown argument {arg}"),
         }
-
-        println!("{fields}");
-
-        Ok(steps / 2)
     }
-}
 
-fn main() -> Result<(), Error> {
-    let a = std::env::args().nth(1).context("Input file missing")?;
+    // These treat the loop as a polygon in the plane, which a loop across
+    // the edges of a wrapping grid is not.
+    if wrap && (check || all_loops || encode || pockets || svg.is_some() || !points.is_empty()) {
+        bail!(
+            "--check, --all-loops, --encode, --pockets, --svg and --point do not support \
+             wrapping grids"
+        );
+    }
 
     let mut builder = SolverBuilder::new();
+    builder.wrap = wrap;
 
     for line in read_lines(a)? {
         let line = line?;
         builder.process_line(&line)?;
     }
 
+    if let Some(max_cost) = max_repair {
+        let repair = builder.repair(max_cost, |_| 1)?;
+        println!("Repair cost: {}", repair.cost);
+        for edit in &repair.edits {
+            println!(
+                "Edit {:?}: {:?} -> {:?} (cost {})",
+                edit.tile, edit.old, edit.new, edit.cost
+            );
+        }
+        builder = repair.builder;
+    }
+
+    // Without a start, the grid is only analyzed as a maze.
+    if all_loops && builder.start.is_none() {
+        print!("{}", analysis::analyze(&builder.pipes)?);
+        return Ok(());
+    }
+
     let solver = builder.build()?;
 
+    if all_loops {
+        print!("{}", analysis::analyze(&solver.pipes)?);
+    }
+
     let solution = solver.solve()?;
 
-    println!("{solution}");
+    println!("Part 1: {}", solution.farthest_distance);
+    println!("Part 2: {}", solution.enclosed);
+    println!("Loop length: {}", solution.loop_length);
+    println!("Start pipe: {:?}", solution.start_pipe);
+
+    if !wrap {
+        let turns = solution.turns();
+        println!("Orientation: {:?}", solution.orientation());
+        println!("Turns: {} left, {} right", turns.left, turns.right);
+    }
+
+    for (row, col) in points {
+        match solution.winding_number(row, col) {
+            Some(winding) => println!(
+                "Point ({row}, {col}): winding number {winding}, inside: {}",
+                solution.contains(row, col)
+            ),
+            None => println!("Point ({row}, {col}): on the loop"),
+        }
+    }
+
+    let loop_tiles = solution
+        .tiles
+        .iter()
+        .flatten()
+        .filter(|t| **t == Tile::Loop);
+    if check && loop_tiles.count() < solution.loop_length {
+        println!("Part 2 (shoelace): skipped, the loop crosses itself");
+    } else if check {
+        let enclosed = solver.enclosed_by_area(&solution);
+        println!("Part 2 (shoelace): {enclosed}");
+        if enclosed != solution.enclosed {
+            bail!("parity scan and shoelace formula disagree");
+        }
+    }
+
+    let distances = solver.distances(&solution);
+    println!("Farthest tiles: {:?}", farthest_tiles(&distances));
+    if print_distances {
+        print!("{}", format_distances(&distances));
+    }
+
+    if encode {
+        println!("Path: {}", solution.encode_path());
+    }
+
+    if let Some(path) = svg {
+        std::fs::write(path, solver.to_svg(&solution))?;
+    }
+
+    if pockets {
+        let pockets = solver.pockets(&solution);
+        for pocket in &pockets {
+            println!(
+                "Pocket of {} tiles from {:?} to {:?}",
+                pocket.size, pocket.min, pocket.max
+            );
+        }
+        let size: usize = pockets.iter().map(|pocket| pocket.size).sum();
+        println!("Pockets: {}, {size} tiles", pockets.len());
+        if size != solution.enclosed {
+            println!("Pockets and parity scan disagree, the loop crosses itself");
+        }
+    }
+
+    if let Some(delay) = animate {
+        solver.animate(&solution, delay, &mut std::io::stdout())?;
+    }
+
+    if let Some(dir) = frames {
+        let count = solver.write_frames(&solution, std::path::Path::new(&dir))?;
+        println!("Wrote {count} frames to {dir}");
+    }
+
+    if let Some(style) = style {
+        print!("{}", solver.render(&solution, style));
+    }
+
+    if print_tiles {
+        for (row, col) in &solution.loop_tiles {
+            println!("{row} {col}");
+        }
+    }
 
     Ok(())
 }
+
+/// Classifies every tile by scanning each row and flipping between outside
+/// and inside whenever a loop tile reaching north is crossed. Where the loop
+/// crosses itself this is the even-odd rule.
+fn scan(pipes: &[Vec<Pipe>], loop_tiles: &[(usize, usize)]) -> Vec<Vec<Tile>> {
+    let visited: HashSet<(usize, usize)> = loop_tiles.iter().copied().collect();
+
+    // Crossings and junctions have more openings than the loop uses, so the
+    // scan looks at the steps of the loop instead of the pipes.
+    let mut reaching_north = HashSet::new();
+    for (a, b) in loop_tiles.iter().zip(loop_tiles.iter().cycle().skip(1)) {
+        match Heading::between(*a, *b) {
+            Heading::North => reaching_north.insert(*a),
+            Heading::South => reaching_north.insert(*b),
+            _ => false,
+        };
+    }
+
+    let mut tiles = Vec::with_capacity(pipes.len());
+    for (row, pipes_row) in pipes.iter().enumerate() {
+        let mut inside = false;
+        let mut line = Vec::with_capacity(pipes_row.len());
+
+        for col in 0..pipes_row.len() {
+            if visited.contains(&(row, col)) {
+                if reaching_north.contains(&(row, col)) {
+                    inside = !inside;
+                }
+                line.push(Tile::Loop);
+            } else if inside {
+                line.push(Tile::Inside);
+            } else {
+                line.push(Tile::Outside);
+            }
+        }
+
+        tiles.push(line);
+    }
+
+    tiles
+}
+
+/// Number of tiles inside a loop with the given corners (or any superset of
+/// them, in walk order) and length, from the shoelace formula and Pick's
+/// theorem.
+fn interior_points(vertices: &[(usize, usize)], length: usize) -> usize {
+    let twice_area: i64 = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64)
+        .sum::<i64>()
+        .abs();
+
+    // Pick: A = I + B / 2 - 1, where every loop tile is a boundary point.
+    ((twice_area - length as i64 + 2) / 2) as usize
+}
+
+/// Tiles with the largest distance from the start.
+fn farthest_tiles(distances: &[Vec<Option<usize>>]) -> Vec<(usize, usize)> {
+    let max = distances.iter().flatten().flatten().max();
+
+    let mut tiles = Vec::new();
+    for (row, line) in distances.iter().enumerate() {
+        for (col, distance) in line.iter().enumerate() {
+            if distance.is_some() && *distance == max.copied() {
+                tiles.push((row, col));
+            }
+        }
+    }
+    tiles
+}
+
+/// Formats the distance grid like the puzzle description, with `.` for
+/// tiles off the loop. Distances with more than one digit are separated by
+/// spaces.
+fn format_distances(distances: &[Vec<Option<usize>>]) -> String {
+    let max = distances.iter().flatten().flatten().max().copied();
+    let width = max.unwrap_or(0).to_string().len();
+    let sep = if width > 1 { " " } else { "" };
+
+    let mut out = String::new();
+    for line in distances {
+        let cells: Vec<String> = line
+            .iter()
+            .map(|distance| match distance {
+                Some(distance) => format!("{distance:>width$}"),
+                None => format!("{:>width$}", "."),
+            })
+            .collect();
+        out += &cells.join(sep);
+        out.push('\n');
+    }
+    out
+}
+
+/// Builder fed with `lines`, which every test starts from.
+#[cfg(test)]
+fn builder_from<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> SolverBuilder {
+    let mut builder = SolverBuilder::new();
+    for line in lines {
+        builder.process_line(line.as_ref()).unwrap();
+    }
+    builder
+}
+
+#[cfg(test)]
+fn read_example(path: &str) -> Vec<String> {
+    read_lines(path).unwrap().map(Result::unwrap).collect()
+}
+
+#[cfg(test)]
+fn solve_lines<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> (Solver, Solution) {
+    let solver = builder_from(lines).build().unwrap();
+    let solution = solver.solve().unwrap();
+    (solver, solution)
+}
+
+#[cfg(test)]
+fn solve_file(path: &str) -> (Solver, Solution) {
+    solve_lines(read_example(path))
+}
+
+#[test]
+fn test_solution() {
+    let (_, solution) = solve_file("example");
+    assert_eq!(solution.loop_length, 72);
+    assert_eq!(solution.farthest_distance, 36);
+    assert_eq!(solution.enclosed, 56);
+    assert_eq!(solution.loop_tiles.len(), solution.loop_length);
+    assert_eq!(solution.loop_tiles[0], (3, 13));
+    assert_eq!(solution.tiles[3][13], Tile::Loop);
+    assert_eq!(solution.tiles[0][0], Tile::Outside);
+    assert!(matches!(solution.start_pipe, Pipe::Vertical));
+
+    let (_, solution) = solve_file("example_2");
+    assert_eq!(solution.farthest_distance, 80);
+    assert_eq!(solution.enclosed, 10);
+    assert!(matches!(solution.start_pipe, Pipe::BendSouthWest));
+}
+
+#[test]
+fn test_disambiguate_start() {
+    let lines = [".....", "-S-7.", "-|.|.", ".L-J.", "....."];
+    let (_, solution) = solve_lines(lines);
+    assert!(matches!(solution.start_pipe, Pipe::BendSouthEast));
+    assert_eq!(solution.loop_length, 8);
+
+    let error = builder_from([".....", "-S-7.", "-|.|.", ".L-..", "....."])
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        [
+            "invalid start, no pair of headings forms a loop",
+            "East/South: walk breaks at (3, 3) heading South into Ground: no path",
+            "East/West: walk breaks at (3, 3) heading South into Ground: no path",
+            "South/West: walk breaks at (3, 3) heading East into Ground: no path",
+        ]
+        .join("\n")
+    );
+
+    let error = builder_from(["...", ".S.", ".|."]).build().unwrap_err();
+    assert_eq!(error.to_string(), "start connects to 1 neighbors");
+}
+
+#[test]
+fn test_bounds_checked_walk() {
+    let error = |lines: &[&str]| {
+        let solver = builder_from(lines).build().unwrap();
+        format!("{:#}", solver.solve().unwrap_err())
+    };
+
+    assert_eq!(
+        error(&["S-7", "|.|", "|.L"]),
+        "walk leaves the grid at (2, 2) heading East from BendNorthEast"
+    );
+    assert_eq!(
+        error(&["S-7", "|.|", "L--"]),
+        "walk breaks at (2, 2) heading South into Horizontal: no path"
+    );
+    assert_eq!(
+        error(&["S-7", "|.|", "L-"]),
+        "walk breaks at (2, 2) heading South into Ground: no path"
+    );
+
+    let lines = [".....", ".S-7", ".|.|.", ".L-J", ""];
+    let (solver, solution) = solve_lines(lines);
+    assert_eq!(solution.loop_length, 8);
+    assert!(solver.pipes.iter().all(|row| row.len() == 5));
+}
+
+#[test]
+fn test_distances() {
+    let lines = ["..F7.", ".FJ|.", "SJ.L7", "|F--J", "LJ..."];
+    let (solver, solution) = solve_lines(lines);
+    let distances = solver.distances(&solution);
+
+    assert_eq!(
+        format_distances(&distances),
+        "..45.\n.236.\n01.78\n14567\n23...\n"
+    );
+    assert_eq!(farthest_tiles(&distances), vec![(2, 4)]);
+    assert_eq!(solution.farthest_distance, 8);
+
+    let (solver, solution) = solve_lines([".....", ".S-7.", ".|.|.", ".L-J.", "....."]);
+    let distances = solver.distances(&solution);
+    assert_eq!(farthest_tiles(&distances), vec![(3, 3)]);
+    assert_eq!(distances[3][3], Some(solution.farthest_distance));
+    assert!(format_distances(&distances).starts_with(".....\n.012.\n"));
+
+    let (solver, solution) = solve_file("example");
+    let distances = solver.distances(&solution);
+    for tile in farthest_tiles(&distances) {
+        assert_eq!(distances[tile.0][tile.1], Some(solution.farthest_distance));
+    }
+
+    // The stems of the junctions are junk and get no distance.
+    let lines = ["S-┬-7", "|.|.|", "L-┴-J"];
+    let (solver, solution) = solve_lines(lines);
+    let distances = solver.distances(&solution);
+    assert_eq!(distances[1][2], None);
+    assert_eq!(distances[0][2], Some(2));
+    assert_eq!(distances[2][2], Some(4));
+}
+
+#[test]
+fn test_enclosed_by_area() {
+    for path in ["example", "example_2"] {
+        let (solver, solution) = solve_file(path);
+        assert_eq!(solver.enclosed_by_area(&solution), solution.enclosed);
+    }
+
+    for seed in 0..200 {
+        let mut rng = generate::Rng::new(seed);
+        let rows = 2 + rng.below(30);
+        let cols = 2 + rng.below(30);
+        let generator = generate::Generator {
+            rows,
+            cols,
+            length: 4 + 2 * rng.below(1 + rows * cols / 5),
+            junk: 0.5,
+            seed,
+        };
+        let maze = generator.generate().unwrap();
+        let (solver, solution) = solve_lines(&maze.lines);
+
+        assert_eq!(solution.loop_length, maze.loop_length, "{:#?}", maze.lines);
+        assert_eq!(solution.farthest_distance, maze.farthest_distance);
+        assert_eq!(solution.enclosed, maze.enclosed, "{:#?}", maze.lines);
+        assert_eq!(solver.enclosed_by_area(&solution), solution.enclosed);
+    }
+}
+
+#[test]
+fn test_crossings_and_junctions() {
+    // A figure eight crossing itself once.
+    let lines = ["S-7..", "|.|..", "L-+-7", "..|.|", "..L-J"];
+    let (_, solution) = solve_lines(lines);
+    assert_eq!(solution.loop_length, 16);
+    assert_eq!(solution.farthest_distance, 8);
+    assert_eq!(solution.enclosed, 2);
+    assert!(solution.contains(1, 1) && solution.contains(3, 3));
+
+    // The loop passes straight along the bars, the stems are junk.
+    let lines = ["S-┬-7", "|.|.|", "L-┴-J"];
+    let (solver, solution) = solve_lines(lines);
+    assert_eq!(solution.loop_length, 12);
+    assert_eq!(solution.enclosed, 3);
+    assert_eq!(solver.enclosed_by_area(&solution), 3);
+
+    // The junction sends the walk north both times, into a cycle that
+    // misses the start.
+    let error = builder_from(["..F-7", "..|.|", "..├-J", "..|..", "..S-7", "....|"])
+        .build()
+        .unwrap()
+        .solve()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "walk leaves (2, 2) heading North again, without returning to the start"
+    );
+
+    // Entering through the stem, the walk turns right.
+    let lines = ["S-7", "|.|", "├-J"];
+    let (_, solution) = solve_lines(lines);
+    assert_eq!(solution.loop_length, 8);
+    assert_eq!(solution.enclosed, 1);
+}
+
+#[test]
+#[ignore = "benchmark, run with `cargo test --release -- --ignored --nocapture`"]
+fn bench_solve() {
+    let generator = generate::Generator {
+        rows: 1000,
+        cols: 1000,
+        length: 200_000,
+        junk: 0.3,
+        seed: 42,
+    };
+
+    let start = std::time::Instant::now();
+    let maze = generator.generate().unwrap();
+    println!("generate: {:?}", start.elapsed());
+
+    let start = std::time::Instant::now();
+    let (_, solution) = solve_lines(&maze.lines);
+    println!("solve: {:?}", start.elapsed());
+
+    assert_eq!(solution.loop_length, maze.loop_length);
+    assert_eq!(solution.enclosed, maze.enclosed);
+}
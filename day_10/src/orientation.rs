@@ -0,0 +1,123 @@
+use crate::{Solution, Tile};
+
+/// Direction in which the loop is walked, as seen on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// Number of bends of the loop, by the direction the walk turns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Turns {
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Solution {
+    /// Whether the loop runs clockwise in walk order, from the sign of the
+    /// shoelace area.
+    pub fn orientation(&self) -> Orientation {
+        let tiles = &self.loop_tiles;
+        let signed_area: i64 = tiles
+            .iter()
+            .zip(tiles.iter().cycle().skip(1))
+            .map(|(a, b)| a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64)
+            .sum();
+
+        // Rows grow downwards, which flips the usual sign.
+        if signed_area < 0 {
+            Orientation::Clockwise
+        } else {
+            Orientation::CounterClockwise
+        }
+    }
+
+    /// Counts the left and right bends of the loop in walk order.
+    pub fn turns(&self) -> Turns {
+        let tiles = &self.loop_tiles;
+        let n = tiles.len();
+        let mut turns = Turns { left: 0, right: 0 };
+
+        for i in 0..n {
+            let prev = tiles[(i + n - 1) % n];
+            let tile = tiles[i];
+            let next = tiles[(i + 1) % n];
+
+            let incoming = (tile.0 as i64 - prev.0 as i64, tile.1 as i64 - prev.1 as i64);
+            let outgoing = (next.0 as i64 - tile.0 as i64, next.1 as i64 - tile.1 as i64);
+
+            match incoming.1 * outgoing.0 - incoming.0 * outgoing.1 {
+                1 => turns.right += 1,
+                -1 => turns.left += 1,
+                _ => {}
+            }
+        }
+
+        turns
+    }
+
+    /// Winding number of the loop around the center of a tile, positive if
+    /// the loop runs counter-clockwise around it. Tiles on the loop have
+    /// none.
+    ///
+    /// A ray is cast eastwards and every vertical loop step it crosses is
+    /// counted by its direction. Steps count for the upper of their two
+    /// rows, so that rays through tile centers are never ambiguous.
+    pub fn winding_number(&self, row: usize, col: usize) -> Option<i64> {
+        if self.tiles.get(row).and_then(|tiles| tiles.get(col)) == Some(&Tile::Loop) {
+            return None;
+        }
+
+        let tiles = &self.loop_tiles;
+        let mut winding = 0;
+
+        for (a, b) in tiles.iter().zip(tiles.iter().cycle().skip(1)) {
+            if a.1 != b.1 || a.1 <= col || a.0.min(b.0) != row {
+                continue;
+            }
+
+            if b.0 > a.0 {
+                winding -= 1;
+            } else {
+                winding += 1;
+            }
+        }
+
+        Some(winding)
+    }
+
+    /// Whether the tile is enclosed by the loop.
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        self.tiles.get(row).and_then(|tiles| tiles.get(col)) == Some(&Tile::Inside)
+    }
+}
+
+#[test]
+fn test_orientation() {
+    let (_, solution) = crate::solve_lines([
+        ".......", ".S---7.", ".|...|.", ".|.F-J.", ".|.|...", ".L-J...", ".......",
+    ]);
+
+    // The walk leaves the start eastwards.
+    assert_eq!(solution.orientation(), Orientation::Clockwise);
+    assert_eq!(solution.turns(), Turns { left: 1, right: 5 });
+
+    assert_eq!(solution.winding_number(2, 2), Some(-1));
+    assert_eq!(solution.winding_number(4, 2), Some(-1));
+    assert_eq!(solution.winding_number(4, 4), Some(0));
+    assert_eq!(solution.winding_number(0, 0), Some(0));
+    assert_eq!(solution.winding_number(1, 1), None);
+
+    assert!(solution.contains(2, 4));
+    assert!(!solution.contains(4, 5));
+    assert!(!solution.contains(3, 3));
+    assert!(!solution.contains(100, 100));
+
+    for row in 0..7 {
+        for col in 0..7 {
+            let inside = solution.winding_number(row, col).is_some_and(|w| w != 0);
+            assert_eq!(inside, solution.contains(row, col));
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Context, Error};
+
+use crate::{Heading, Pipe, Solution};
+
+impl Heading {
+    fn letter(self) -> char {
+        match self {
+            Heading::North => 'N',
+            Heading::East => 'E',
+            Heading::South => 'S',
+            Heading::West => 'W',
+        }
+    }
+
+    pub fn between(from: (usize, usize), to: (usize, usize)) -> Self {
+        if to.0 < from.0 {
+            Heading::North
+        } else if to.0 > from.0 {
+            Heading::South
+        } else if to.1 > from.1 {
+            Heading::East
+        } else {
+            Heading::West
+        }
+    }
+}
+
+impl Solution {
+    /// Encodes the loop as runs of steps from the start, like
+    /// `S E3 S2 W3 N2`. The leading `S` marks the start, every run has an
+    /// explicit count.
+    pub fn encode_path(&self) -> String {
+        let tiles = &self.loop_tiles;
+        let mut runs: Vec<(Heading, usize)> = Vec::new();
+
+        for (from, to) in tiles.iter().zip(tiles.iter().cycle().skip(1)) {
+            let heading = Heading::between(*from, *to);
+            match runs.last_mut() {
+                Some((last, count)) if *last == heading => *count += 1,
+                _ => runs.push((heading, 1)),
+            }
+        }
+
+        let mut path = String::from("S");
+        for (heading, count) in runs {
+            path += &format!(" {}{count}", heading.letter());
+        }
+        path
+    }
+}
+
+/// Turns a path from `Solution::encode_path` into the lines of a pipe grid,
+/// just large enough to hold the loop.
+pub fn decode_path(path: &str) -> Result<Vec<String>, Error> {
+    let mut tokens = path.split_whitespace();
+    if tokens.next() != Some("S") {
+        bail!("path must begin with the start marker S");
+    }
+
+    let mut steps = Vec::new();
+    for token in tokens {
+        let mut chars = token.chars();
+        let heading = match chars.next() {
+            Some('N') => Heading::North,
+            Some('E') => Heading::East,
+            Some('S') => Heading::South,
+            Some('W') => Heading::West,
+            _ => bail!("invalid run {token}"),
+        };
+        let count: usize = chars
+            .as_str()
+            .parse()
+            .with_context(|| format!("invalid count in run {token}"))?;
+
+        steps.extend(std::iter::repeat_n(heading, count));
+    }
+
+    if steps.len() < 4 {
+        bail!("a loop needs at least four steps");
+    }
+
+    // Walk the path relative to the start first, to find the bounding box.
+    let mut position = (0i64, 0i64);
+    let mut positions = vec![position];
+    for heading in &steps {
+        let offset = heading.to_offsets();
+        position = (position.0 + offset.0, position.1 + offset.1);
+        positions.push(position);
+    }
+
+    if positions.pop() != Some((0, 0)) {
+        bail!("path does not return to the start");
+    }
+    let mut seen = HashSet::new();
+    for position in &positions {
+        if !seen.insert(*position) {
+            bail!("path crosses itself at {position:?} relative to the start");
+        }
+    }
+
+    let min_row = positions.iter().map(|p| p.0).min().unwrap_or(0);
+    let max_row = positions.iter().map(|p| p.0).max().unwrap_or(0);
+    let min_col = positions.iter().map(|p| p.1).min().unwrap_or(0);
+    let max_col = positions.iter().map(|p| p.1).max().unwrap_or(0);
+
+    let mut grid =
+        vec![vec!['.'; (max_col - min_col + 1) as usize]; (max_row - min_row + 1) as usize];
+
+    for (i, position) in positions.iter().enumerate() {
+        let incoming = steps[(i + steps.len() - 1) % steps.len()];
+        let outgoing = steps[i];
+        let pipe = Pipe::try_from(&[outgoing, incoming.opposite()])?;
+
+        let row = (position.0 - min_row) as usize;
+        let col = (position.1 - min_col) as usize;
+        grid[row][col] = if i == 0 { 'S' } else { pipe.ascii() };
+    }
+
+    Ok(grid.into_iter().map(String::from_iter).collect())
+}
+
+#[test]
+fn test_path_round_trip() {
+    let lines = decode_path("S E3 S2 W3 N2").unwrap();
+    assert_eq!(lines, vec!["S--7", "|..|", "L--J"]);
+
+    let (_, solution) = crate::solve_lines(&lines);
+    assert_eq!(solution.encode_path(), "S E3 S2 W3 N2");
+    assert_eq!(solution.enclosed, 2);
+
+    for path in ["example", "example_2"] {
+        let (_, solution) = crate::solve_file(path);
+        let encoded = solution.encode_path();
+
+        let (_, decoded) = crate::solve_lines(decode_path(&encoded).unwrap());
+        assert_eq!(decoded.encode_path(), encoded);
+        assert_eq!(decoded.loop_length, solution.loop_length);
+        assert_eq!(decoded.enclosed, solution.enclosed);
+    }
+
+    assert!(decode_path("E3 S2 W3 N2").is_err());
+    assert!(decode_path("S E3 S2 W3 N1").is_err());
+    assert!(decode_path("S E2 S1 W1 N1 W1 S1 E1 N1 W1").is_err());
+    assert!(decode_path("S E3 S2 X3 N2").is_err());
+}
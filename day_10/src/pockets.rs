@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+
+use crate::{Heading, Solution, Solver};
+
+/// Connected region of tiles enclosed by the loop.
+#[derive(Debug, PartialEq)]
+pub struct Pocket {
+    /// Number of tiles in the pocket.
+    pub size: usize,
+    /// Top left corner of the bounding box, as (row, column).
+    pub min: (usize, usize),
+    /// Bottom right corner of the bounding box, as (row, column).
+    pub max: (usize, usize),
+}
+
+impl Solver {
+    /// Splits the tiles enclosed by the loop into connected pockets, in the
+    /// order of their first tile.
+    ///
+    /// Whether a tile is enclosed is decided at twice the resolution, with a
+    /// cell for every tile and one for the gap between every two neighbors,
+    /// where the loop walls off the tiles and gaps it runs through. Flood
+    /// filling the free cells lets regions squeeze between parallel pipes,
+    /// and the tiles of every region that does not reach the edge of the grid
+    /// are enclosed. For a loop that crosses itself, this can include tiles
+    /// the parity scan counts as outside. The pockets are then the groups of
+    /// enclosed tiles that touch side by side, without squeezing.
+    pub fn pockets(&self, solution: &Solution) -> Vec<Pocket> {
+        let inside = self.enclosed_tiles(solution);
+        let mut pockets = Vec::new();
+        let mut seen = vec![vec![false; inside.first().map_or(0, Vec::len)]; inside.len()];
+
+        for (row, inside_row) in inside.iter().enumerate() {
+            for (col, &is_inside) in inside_row.iter().enumerate() {
+                if !is_inside || seen[row][col] {
+                    continue;
+                }
+
+                let mut pocket = Pocket {
+                    size: 0,
+                    min: (usize::MAX, usize::MAX),
+                    max: (0, 0),
+                };
+                let mut queue = VecDeque::from([(row, col)]);
+                seen[row][col] = true;
+
+                while let Some(tile) = queue.pop_front() {
+                    pocket.size += 1;
+                    pocket.min = (pocket.min.0.min(tile.0), pocket.min.1.min(tile.1));
+                    pocket.max = (pocket.max.0.max(tile.0), pocket.max.1.max(tile.1));
+
+                    for next in neighbors(tile, inside.len(), inside_row.len()) {
+                        if inside[next.0][next.1] && !seen[next.0][next.1] {
+                            seen[next.0][next.1] = true;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+
+                pockets.push(pocket);
+            }
+        }
+
+        pockets
+    }
+
+    /// Marks the tiles whose region cannot squeeze out to the edge of the
+    /// grid.
+    fn enclosed_tiles(&self, solution: &Solution) -> Vec<Vec<bool>> {
+        let rows = 2 * self.pipes.len() + 1;
+        let cols = 2 * self.pipes.first().map_or(0, Vec::len) + 1;
+        let mut blocked = vec![vec![false; cols]; rows];
+
+        let tiles = &solution.loop_tiles;
+        for (a, b) in tiles.iter().zip(tiles.iter().cycle().skip(1)) {
+            blocked[2 * a.0 + 1][2 * a.1 + 1] = true;
+            blocked[a.0 + b.0 + 1][a.1 + b.1 + 1] = true;
+        }
+
+        let mut inside = vec![vec![false; cols / 2]; rows / 2];
+        let mut seen = vec![vec![false; cols]; rows];
+
+        for row in (1..rows).step_by(2) {
+            for col in (1..cols).step_by(2) {
+                if blocked[row][col] || seen[row][col] {
+                    continue;
+                }
+
+                let mut region = Vec::new();
+                let mut escapes = false;
+                let mut queue = VecDeque::from([(row, col)]);
+                seen[row][col] = true;
+
+                while let Some(cell) = queue.pop_front() {
+                    if cell.0 == 0 || cell.0 == rows - 1 || cell.1 == 0 || cell.1 == cols - 1 {
+                        escapes = true;
+                    }
+                    if cell.0 % 2 == 1 && cell.1 % 2 == 1 {
+                        region.push((cell.0 / 2, cell.1 / 2));
+                    }
+
+                    for next in neighbors(cell, rows, cols) {
+                        if !blocked[next.0][next.1] && !seen[next.0][next.1] {
+                            seen[next.0][next.1] = true;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+
+                if !escapes {
+                    for tile in region {
+                        inside[tile.0][tile.1] = true;
+                    }
+                }
+            }
+        }
+
+        inside
+    }
+}
+
+/// Cells next to `cell` in a grid of `rows` by `cols`.
+fn neighbors(
+    cell: (usize, usize),
+    rows: usize,
+    cols: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    [Heading::North, Heading::East, Heading::South, Heading::West]
+        .into_iter()
+        .filter_map(move |heading| {
+            let offset = heading.to_offsets();
+            let next = (cell.0 as i64 + offset.0, cell.1 as i64 + offset.1);
+            if next.0 < 0 || next.1 < 0 || next.0 >= rows as i64 || next.1 >= cols as i64 {
+                return None;
+            }
+            Some((next.0 as usize, next.1 as usize))
+        })
+}
+
+#[test]
+fn test_pockets() {
+    // The middle region squeezes out between the pipes, while the two halves
+    // at the bottom stay enclosed but only touch by squeezing, so they are
+    // separate pockets.
+    let lines = [
+        "..........",
+        ".S------7.",
+        ".|F----7|.",
+        ".||....||.",
+        ".||....||.",
+        ".|L-7F-J|.",
+        ".|..||..|.",
+        ".L--JL--J.",
+        "..........",
+    ];
+    let (solver, solution) = crate::solve_lines(lines);
+    assert_eq!(
+        solver.pockets(&solution),
+        vec![
+            Pocket {
+                size: 2,
+                min: (6, 2),
+                max: (6, 3)
+            },
+            Pocket {
+                size: 2,
+                min: (6, 6),
+                max: (6, 7)
+            }
+        ]
+    );
+
+    // The lobes of a figure eight are separate pockets.
+    let lines = ["S-7..", "|.|..", "L-+-7", "..|.|", "..L-J"];
+    let (solver, solution) = crate::solve_lines(lines);
+    let pockets = solver.pockets(&solution);
+    assert_eq!(pockets.len(), 2);
+    assert_eq!((pockets[0].min, pockets[1].min), ((1, 1), (3, 3)));
+
+    for path in ["example", "example_2"] {
+        let (solver, solution) = crate::solve_file(path);
+        let pockets = solver.pockets(&solution);
+        let size: usize = pockets.iter().map(|pocket| pocket.size).sum();
+        assert_eq!(size, solution.enclosed);
+        if path == "example_2" {
+            assert!(pockets.len() > 1);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Error};
+
+use crate::{Pipe, Solution, Solver, Tile};
+
+/// How `Solver::render` draws the grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    /// Loop drawn with the puzzle's own characters.
+    Ascii,
+    /// Loop drawn with box-drawing characters.
+    Unicode,
+    /// Box-drawing characters with the inside shaded by ANSI colors.
+    Ansi,
+}
+
+impl FromStr for Style {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ascii" => Self::Ascii,
+            "unicode" => Self::Unicode,
+            "ansi" => Self::Ansi,
+            _ => bail!("invalid style {s}, expected ascii, unicode or ansi"),
+        })
+    }
+}
+
+impl Pipe {
+    pub fn ascii(self) -> char {
+        match self {
+            Pipe::Horizontal => '-',
+            Pipe::Vertical => '|',
+            Pipe::BendSouthWest => '7',
+            Pipe::BendSouthEast => 'F',
+            Pipe::BendNorthWest => 'J',
+            Pipe::BendNorthEast => 'L',
+            Pipe::Crossing => '+',
+            // Junctions have no ASCII look-alike.
+            Pipe::TeeNorth => '┴',
+            Pipe::TeeEast => '├',
+            Pipe::TeeSouth => '┬',
+            Pipe::TeeWest => '┤',
+            Pipe::Ground => '.',
+        }
+    }
+
+    pub fn box_drawing(self) -> char {
+        match self {
+            Pipe::Horizontal => '─',
+            Pipe::Vertical => '│',
+            Pipe::BendSouthWest => '┐',
+            Pipe::BendSouthEast => '┌',
+            Pipe::BendNorthWest => '┘',
+            Pipe::BendNorthEast => '└',
+            Pipe::Crossing => '┼',
+            Pipe::TeeNorth => '┴',
+            Pipe::TeeEast => '├',
+            Pipe::TeeSouth => '┬',
+            Pipe::TeeWest => '┤',
+            Pipe::Ground => ' ',
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const LOOP: &str = "\x1b[1;33m";
+const INSIDE: &str = "\x1b[30;42m";
+const OUTSIDE: &str = "\x1b[34m";
+const JUNK_INSIDE: &str = "\x1b[2;42m";
+const JUNK_OUTSIDE: &str = "\x1b[2m";
+
+impl Solver {
+    /// Draws the loop found by `solve` and marks every other tile as inside
+    /// (`I`) or outside (`O`). Junk pipes that are not part of the loop are
+    /// marked in lower case (`i` and `o`).
+    ///
+    /// With `Style::Ansi` junk pipes keep their glyph and the inside is shaded
+    /// instead.
+    pub fn render(&self, solution: &Solution, style: Style) -> String {
+        let mut out = String::new();
+
+        for (pipes, tiles) in self.pipes.iter().zip(&solution.tiles) {
+            for (pipe, tile) in pipes.iter().zip(tiles) {
+                let junk = !matches!(pipe, Pipe::Ground);
+
+                match style {
+                    Style::Ascii | Style::Unicode => {
+                        out.push(match (tile, junk) {
+                            (Tile::Loop, _) if style == Style::Ascii => pipe.ascii(),
+                            (Tile::Loop, _) => pipe.box_drawing(),
+                            (Tile::Inside, true) => 'i',
+                            (Tile::Outside, true) => 'o',
+                            (Tile::Inside, false) => 'I',
+                            (Tile::Outside, false) => 'O',
+                        });
+                    }
+                    Style::Ansi => {
+                        let (color, ch) = match (tile, junk) {
+                            (Tile::Loop, _) => (LOOP, pipe.box_drawing()),
+                            (Tile::Inside, true) => (JUNK_INSIDE, pipe.box_drawing()),
+                            (Tile::Outside, true) => (JUNK_OUTSIDE, pipe.box_drawing()),
+                            (Tile::Inside, false) => (INSIDE, 'I'),
+                            (Tile::Outside, false) => (OUTSIDE, 'O'),
+                        };
+                        out.push_str(color);
+                        out.push(ch);
+                        out.push_str(RESET);
+                    }
+                }
+            }
+
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+#[test]
+fn test_render() {
+    let (solver, solution) = crate::solve_lines([
+        "..........",
+        ".S------7.",
+        ".|F----7|.",
+        ".||-...||.",
+        ".||....||.",
+        ".|L-7F-J|.",
+        ".|..||..|.",
+        ".L--JL--J.",
+        "..........",
+    ]);
+
+    assert_eq!(
+        solver.render(&solution, Style::Unicode),
+        concat!(
+            "OOOOOOOOOO\n",
+            "O┌──────┐O\n",
+            "O│┌────┐│O\n",
+            "O││oOOO││O\n",
+            "O││OOOO││O\n",
+            "O│└─┐┌─┘│O\n",
+            "O│II││II│O\n",
+            "O└──┘└──┘O\n",
+            "OOOOOOOOOO\n",
+        )
+    );
+    assert_eq!(
+        solver.render(&solution, Style::Ascii).lines().nth(5),
+        Some("O|L-7F-J|O")
+    );
+    assert!(solver
+        .render(&solution, Style::Ansi)
+        .starts_with("\x1b[34mO\x1b[0m"));
+}
@@ -0,0 +1,330 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use anyhow::{bail, Context, Error};
+
+use crate::{step, Heading, Pipe, SolverBuilder};
+
+const HEADINGS: [Heading; 4] = [Heading::North, Heading::East, Heading::South, Heading::West];
+const NONE: usize = usize::MAX;
+
+/// Single tile changed by a repair.
+#[derive(Debug)]
+pub struct Edit {
+    pub tile: (usize, usize),
+    pub old: Pipe,
+    pub new: Pipe,
+    pub cost: usize,
+}
+
+/// Result of `SolverBuilder::repair`.
+#[derive(Debug)]
+pub struct Repair {
+    pub edits: Vec<Edit>,
+    /// Total cost of all edits.
+    pub cost: usize,
+    /// Builder for the repaired grid.
+    pub builder: SolverBuilder,
+}
+
+impl SolverBuilder {
+    /// Finds the cheapest set of tile changes that closes a loop through the
+    /// start, where changing a tile costs `cost(tile)`. Repairs costing more
+    /// than `max_cost` are not searched.
+    ///
+    /// A shortest path search runs over (tile, exit heading) states: moving
+    /// through a tile along its existing pipe is free, any other way through
+    /// it costs an edit. The cheapest walk from the start back to the start
+    /// is kept whole if it only comes back to crossings, to pass them along
+    /// their other axis. Otherwise it is made simple by erasing the detours
+    /// it takes through tiles it had already visited. That can reshape a tile
+    /// the walk passed for free, so the cost is checked again afterwards.
+    pub fn repair(
+        &self,
+        max_cost: usize,
+        cost: impl Fn((usize, usize)) -> usize,
+    ) -> Result<Repair, Error> {
+        let start = self.start.context("no start")?;
+        if self.wrap {
+            bail!("repairing wrapping grids is not supported");
+        }
+
+        let mut pipes = self.pipes.clone();
+        let width = pipes.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut pipes {
+            row.resize(width, Pipe::Ground);
+        }
+
+        let through = |tile: (usize, usize), entering: Heading, exit: Heading| {
+            Pipe::try_from(&[entering.opposite(), exit]).ok()?;
+            Some(
+                if pipes[tile.0][tile.1].next_heading(entering).ok() == Some(exit) {
+                    0
+                } else {
+                    cost(tile)
+                },
+            )
+        };
+
+        let mut best: Option<(usize, Vec<(usize, usize)>)> = None;
+
+        for first in HEADINGS {
+            let Some(walk) = cheapest_walk(&pipes, start, first, max_cost, &through) else {
+                continue;
+            };
+            if best.as_ref().is_none_or(|(cost, _)| walk.0 < *cost) {
+                best = Some(walk);
+            }
+        }
+
+        let Some((_, walk)) = best else {
+            bail!("no repair costing at most {max_cost} closes a loop through the start");
+        };
+        let cycle = if crosses_cleanly(&pipes, &walk) {
+            walk
+        } else {
+            erase_loops(&walk)
+        };
+
+        let mut edits = Vec::new();
+        let n = cycle.len();
+        for i in 1..n {
+            let tile = cycle[i];
+            let entering = Heading::between(cycle[i - 1], tile);
+            let exit = Heading::between(tile, cycle[(i + 1) % n]);
+            let new = Pipe::try_from(&[entering.opposite(), exit])?;
+            let old = pipes[tile.0][tile.1];
+
+            if old.next_heading(entering).ok() != Some(exit) {
+                pipes[tile.0][tile.1] = new;
+                edits.push(Edit {
+                    tile,
+                    old,
+                    new,
+                    cost: cost(tile),
+                });
+            }
+        }
+
+        let cost = edits.iter().map(|edit| edit.cost).sum();
+        if cost > max_cost {
+            bail!("no repair costing at most {max_cost} closes a loop through the start");
+        }
+
+        Ok(Repair {
+            cost,
+            edits,
+            builder: SolverBuilder {
+                pipes,
+                start: Some(start),
+                wrap: false,
+            },
+        })
+    }
+}
+
+/// Dijkstra from the start, leaving it towards `first`, back to the start.
+/// Returns the cost and the tiles of the cheapest walk, beginning with the
+/// start.
+fn cheapest_walk(
+    pipes: &[Vec<Pipe>],
+    start: (usize, usize),
+    first: Heading,
+    max_cost: usize,
+    through: &impl Fn((usize, usize), Heading, Heading) -> Option<usize>,
+) -> Option<(usize, Vec<(usize, usize)>)> {
+    let width = pipes.first().map_or(0, Vec::len);
+    let state = |tile: (usize, usize), exit: Heading| (tile.0 * width + tile.1) * 4 + exit as usize;
+
+    let mut dist = vec![usize::MAX; pipes.len() * width * 4];
+    let mut parent = vec![NONE; pipes.len() * width * 4];
+    let mut queue = BinaryHeap::new();
+
+    let tile = step(pipes, start, first, false)?;
+    for exit in HEADINGS {
+        if let Some(cost) = through(tile, first, exit) {
+            if cost <= max_cost && cost < dist[state(tile, exit)] {
+                dist[state(tile, exit)] = cost;
+                queue.push(Reverse((cost, tile, exit as usize)));
+            }
+        }
+    }
+
+    while let Some(Reverse((cost, tile, exit))) = queue.pop() {
+        let heading = HEADINGS[exit];
+        if cost > dist[state(tile, heading)] {
+            continue;
+        }
+
+        let Some(next) = step(pipes, tile, heading, false) else {
+            continue;
+        };
+
+        if next == start {
+            if heading.opposite() == first {
+                continue;
+            }
+
+            let mut tiles = vec![tile];
+            let mut current = state(tile, heading);
+            while parent[current] != NONE {
+                current = parent[current];
+                let id = current / 4;
+                tiles.push((id / width, id % width));
+            }
+            tiles.push(start);
+            tiles.reverse();
+
+            return Some((cost, tiles));
+        }
+
+        for next_exit in HEADINGS {
+            let Some(step_cost) = through(next, heading, next_exit) else {
+                continue;
+            };
+
+            let next_cost = cost + step_cost;
+            let next_state = state(next, next_exit);
+            if next_cost <= max_cost && next_cost < dist[next_state] {
+                dist[next_state] = next_cost;
+                parent[next_state] = state(tile, heading);
+                queue.push(Reverse((next_cost, next, next_exit as usize)));
+            }
+        }
+    }
+
+    None
+}
+
+/// Whether a closed walk is a loop as it is: the only tiles it comes back to
+/// are crossings, which it passes straight along both axes.
+fn crosses_cleanly(pipes: &[Vec<Pipe>], walk: &[(usize, usize)]) -> bool {
+    let n = walk.len();
+    let mut passes: HashMap<(usize, usize), Vec<(Heading, Heading)>> = HashMap::new();
+
+    for i in 0..n {
+        let entering = Heading::between(walk[(i + n - 1) % n], walk[i]);
+        let exit = Heading::between(walk[i], walk[(i + 1) % n]);
+        passes.entry(walk[i]).or_default().push((entering, exit));
+    }
+
+    passes.iter().all(|(tile, passes)| match passes.as_slice() {
+        [_] => true,
+        [(a, a_exit), (b, b_exit)] => {
+            pipes[tile.0][tile.1] == Pipe::Crossing
+                && a == a_exit
+                && b == b_exit
+                && *a != *b
+                && *a != b.opposite()
+        }
+        _ => false,
+    })
+}
+
+/// Removes every detour of a closed walk that comes back to a tile it
+/// already passed, which leaves a simple cycle.
+fn erase_loops(walk: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut cycle: Vec<(usize, usize)> = Vec::new();
+
+    for tile in walk {
+        match cycle.iter().position(|t| t == tile) {
+            Some(index) => cycle.truncate(index + 1),
+            None => cycle.push(*tile),
+        }
+    }
+
+    cycle
+}
+
+#[test]
+fn test_repair() {
+    let lines = crate::read_example("example_2");
+    let mut broken = lines.clone();
+    broken[0].replace_range(7..8, ".");
+    broken[9].replace_range(10..11, "-");
+    assert!(crate::builder_from(&broken)
+        .build()
+        .unwrap()
+        .solve()
+        .is_err());
+
+    // Closing a small loop next to the start is cheaper than mending the
+    // big one.
+    let repair = crate::builder_from(&broken).repair(5, |_| 1).unwrap();
+    assert_eq!(repair.cost, 1);
+    assert_eq!(repair.edits[0].tile, (0, 5));
+    let solution = repair.builder.build().unwrap().solve().unwrap();
+    assert_eq!(solution.loop_length, 6);
+
+    assert!(crate::builder_from(&broken).repair(0, |_| 1).is_err());
+
+    // Making every other tile expensive forces the original repair.
+    let repair = crate::builder_from(&broken)
+        .repair(5, |tile| {
+            if [(0, 7), (9, 10)].contains(&tile) {
+                1
+            } else {
+                10
+            }
+        })
+        .unwrap();
+    assert_eq!(repair.cost, 2);
+    let solution = repair.builder.build().unwrap().solve().unwrap();
+    assert_eq!(solution.farthest_distance, 80);
+    assert_eq!(solution.enclosed, 10);
+
+    // An intact figure eight passes its crossing twice and needs no edits.
+    let figure_eight = ["S-7..", "|.|..", "L-+-7", "..|.|", "..L-J"];
+    for lines in [lines.clone(), figure_eight.map(String::from).to_vec()] {
+        let repair = crate::builder_from(&lines).repair(5, |_| 1).unwrap();
+        assert!(repair.edits.is_empty());
+    }
+
+    // Mending the broken figure eight keeps its crossing.
+    let repair = crate::builder_from(["S-7..", "|.|..", "L.+-7", "..|.|", "..L-J"])
+        .repair(1, |_| 1)
+        .unwrap();
+    assert_eq!(repair.cost, 1);
+    assert_eq!(repair.edits[0].tile, (2, 1));
+    let solution = repair.builder.build().unwrap().solve().unwrap();
+    assert_eq!(solution.loop_length, 16);
+
+    // Ground is expensive, so the detour over existing pipes is cheaper.
+    let repair = crate::builder_from(["F-7", "S.|", "|..", "L-J"])
+        .repair(100, |(row, col)| if (row, col) == (2, 2) { 10 } else { 1 })
+        .unwrap();
+    assert_eq!(repair.cost, 2);
+    let tiles: Vec<(usize, usize)> = repair.edits.iter().map(|edit| edit.tile).collect();
+    assert_eq!(tiles.len(), 2);
+    assert!(!tiles.contains(&(2, 2)));
+}
+
+#[test]
+fn test_repair_generated() {
+    for seed in 0..50 {
+        let generator = crate::generate::Generator {
+            rows: 12,
+            cols: 12,
+            length: 40,
+            junk: 0.0,
+            seed,
+        };
+        let maze = generator.generate().unwrap();
+
+        let mut rng = crate::generate::Rng::new(seed);
+        let mut lines = maze.lines.clone();
+        for _ in 0..2 {
+            let row = rng.below(12);
+            let col = rng.below(12);
+            if lines[row].as_bytes()[col] != b'S' {
+                lines[row].replace_range(col..col + 1, ".");
+            }
+        }
+
+        let repair = crate::builder_from(&lines).repair(2, |_| 1).unwrap();
+        assert!(repair.cost <= 2);
+        repair.builder.build().unwrap().solve().unwrap();
+    }
+}
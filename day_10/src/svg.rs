@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use crate::{Solution, Solver, Tile};
+
+/// Edge length of a tile in pixels.
+const SCALE: usize = 10;
+
+impl Solver {
+    /// Draws the loop found by `solve` as an SVG image: the loop is a closed
+    /// polyline through the tile centers, the enclosed tiles are filled and
+    /// the start is marked with a circle.
+    pub fn to_svg(&self, solution: &Solution) -> String {
+        let height = self.pipes.len() * SCALE;
+        let width = self.pipes.first().map_or(0, Vec::len) * SCALE;
+        let center =
+            |(row, col): (usize, usize)| (col * SCALE + SCALE / 2, row * SCALE + SCALE / 2);
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        );
+        let _ = writeln!(
+            svg,
+            r##"  <rect width="{width}" height="{height}" fill="#ffffff"/>"##
+        );
+
+        let _ = writeln!(svg, r##"  <g fill="#9fd89f">"##);
+        for (row, tiles) in solution.tiles.iter().enumerate() {
+            for (col, tile) in tiles.iter().enumerate() {
+                if *tile == Tile::Inside {
+                    let _ = writeln!(
+                        svg,
+                        r#"    <rect x="{}" y="{}" width="{SCALE}" height="{SCALE}"/>"#,
+                        col * SCALE,
+                        row * SCALE
+                    );
+                }
+            }
+        }
+        let _ = writeln!(svg, "  </g>");
+
+        let mut vertices = self.vertices(solution);
+        if let Some(first) = vertices.first().copied() {
+            vertices.push(first);
+        }
+        let points: Vec<String> = vertices
+            .into_iter()
+            .map(center)
+            .map(|(x, y)| format!("{x},{y}"))
+            .collect();
+        let _ = writeln!(
+            svg,
+            r##"  <polyline points="{}" fill="none" stroke="#1f3f9f" stroke-width="2" stroke-linejoin="round"/>"##,
+            points.join(" ")
+        );
+
+        let (x, y) = center(self.start);
+        let _ = writeln!(
+            svg,
+            r##"  <circle cx="{x}" cy="{y}" r="{}" fill="#d03030"/>"##,
+            SCALE / 3
+        );
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+}
+
+#[test]
+fn test_to_svg() {
+    let (solver, solution) = crate::solve_lines(["......", ".S--7.", ".|..|.", ".L--J.", "......"]);
+    let svg = solver.to_svg(&solution);
+
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="60" height="50""#));
+    assert!(svg.contains(r#"<polyline points="15,15 45,15 45,35 15,35 15,15""#));
+    assert!(svg.contains(r#"<rect x="20" y="20" width="10" height="10"/>"#));
+    assert!(svg.contains(r#"<circle cx="15" cy="15""#));
+    assert_eq!(svg.matches("<rect x=").count(), solution.enclosed);
+    assert!(svg.ends_with("</svg>\n"));
+}
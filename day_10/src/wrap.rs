@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Error};
+
+use crate::{step, Heading, Solver, Tile};
+
+impl Solver {
+    /// Classifies every tile of a wrapping grid.
+    ///
+    /// The loop is first unrolled onto the plane, following its steps
+    /// across the edges. A loop that comes back to where it started there
+    /// can be shrunk to a point, and it encloses the tiles that the parity
+    /// scan finds inside the unrolled copy. A loop that comes back shifted
+    /// by whole grid sizes winds around the torus instead. Such a loop does
+    /// not separate an inside from an outside, so it is rejected.
+    pub fn scan_wrapping(&self, loop_tiles: &[(usize, usize)]) -> Result<Vec<Vec<Tile>>, Error> {
+        let rows = self.pipes.len() as i64;
+        let cols = self.pipes[0].len() as i64;
+
+        let mut position = (self.start.0 as i64, self.start.1 as i64);
+        let mut positions = HashSet::new();
+        let mut reaching_north = HashSet::new();
+
+        for (a, b) in loop_tiles.iter().zip(loop_tiles.iter().cycle().skip(1)) {
+            let heading = [Heading::North, Heading::East, Heading::South, Heading::West]
+                .into_iter()
+                .find(|heading| step(&self.pipes, *a, *heading, true) == Some(*b))
+                .expect("the walk only steps to neighbors");
+            let offset = heading.to_offsets();
+            let next = (position.0 + offset.0, position.1 + offset.1);
+
+            positions.insert(position);
+            match heading {
+                Heading::North => reaching_north.insert(position),
+                Heading::South => reaching_north.insert(next),
+                _ => false,
+            };
+            position = next;
+        }
+
+        let shift = (
+            position.0 - self.start.0 as i64,
+            position.1 - self.start.1 as i64,
+        );
+        if shift != (0, 0) {
+            bail!(
+                "the loop winds around the wrapping grid {} times vertically and {} times \
+                 horizontally, so it has no inside to count",
+                shift.0 / rows,
+                shift.1 / cols
+            );
+        }
+
+        let mut tiles: Vec<Vec<Tile>> = self
+            .pipes
+            .iter()
+            .map(|row| vec![Tile::Outside; row.len()])
+            .collect();
+        for (row, col) in loop_tiles {
+            tiles[*row][*col] = Tile::Loop;
+        }
+
+        let min_row = positions.iter().map(|p| p.0).min().unwrap_or(0);
+        let max_row = positions.iter().map(|p| p.0).max().unwrap_or(0);
+        let min_col = positions.iter().map(|p| p.1).min().unwrap_or(0);
+        let max_col = positions.iter().map(|p| p.1).max().unwrap_or(0);
+
+        for row in min_row..=max_row {
+            let mut inside = false;
+
+            for col in min_col..=max_col {
+                if positions.contains(&(row, col)) {
+                    if reaching_north.contains(&(row, col)) {
+                        inside = !inside;
+                    }
+                    continue;
+                }
+
+                let tile = &mut tiles[row.rem_euclid(rows) as usize][col.rem_euclid(cols) as usize];
+                if inside && *tile != Tile::Loop {
+                    *tile = Tile::Inside;
+                }
+            }
+        }
+
+        Ok(tiles)
+    }
+}
+
+#[test]
+fn test_wrapping() {
+    let solve = |mut builder: crate::SolverBuilder| {
+        builder.wrap = true;
+        builder.build()?.solve()
+    };
+
+    // The loop runs across the east and west edges.
+    let solution = solve(crate::builder_from(["-7.S", ".|.|", "-J.L", "...."])).unwrap();
+    assert_eq!(solution.loop_length, 8);
+    assert_eq!(solution.farthest_distance, 4);
+    assert_eq!(solution.enclosed, 1);
+    assert!(solution.contains(1, 0));
+
+    // Across the north and south edges as well.
+    let solution = solve(crate::builder_from(["|..|", "L--J", "....", "F--S"])).unwrap();
+    assert_eq!(solution.loop_length, 10);
+    assert_eq!(solution.enclosed, 2);
+    assert!(solution.contains(0, 1) && solution.contains(0, 2));
+
+    let error = solve(crate::builder_from(["S--", "...", "..."])).unwrap_err();
+    assert!(format!("{error}").contains("winds around"), "{error}");
+
+    let solution = solve(crate::builder_from(crate::read_example("example_2"))).unwrap();
+    assert_eq!(solution.farthest_distance, 80);
+    assert_eq!(solution.enclosed, 10);
+}
@@ -1,7 +1,12 @@
 use std::fmt::Display;
+use std::str::FromStr;
 use std::{char, fmt::Write, path::Path};
 
-use anyhow::{bail, Error};
+use anyhow::{bail, Context, Error};
+
+mod metric;
+
+use metric::Metric;
 
 #[derive(Clone, Copy)]
 enum Element {
@@ -30,9 +35,83 @@ impl Display for Element {
     }
 }
 
+/// How much the empty rows and columns grow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Factors {
+    /// Factor for empty rows, which stretch the universe vertically.
+    rows: usize,
+    /// Factor for empty columns, which stretch the universe horizontally.
+    cols: usize,
+}
+
+impl Factors {
+    fn uniform(factor: usize) -> Self {
+        Self {
+            rows: factor,
+            cols: factor,
+        }
+    }
+}
+
+impl FromStr for Factors {
+    type Err = Error;
+
+    /// Parses `FACTOR`, or `ROWS,COLS` for separate factors.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let factors = match s.split_once(',') {
+            Some((rows, cols)) => Self {
+                rows: rows.parse()?,
+                cols: cols.parse()?,
+            },
+            None => Self::uniform(s.parse()?),
+        };
+
+        if factors.rows == 0 || factors.cols == 0 {
+            bail!("expansion factors must be at least 1");
+        }
+
+        Ok(factors)
+    }
+}
+
+impl Display for Factors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.rows == self.cols {
+            write!(f, "{}", self.rows)
+        } else {
+            write!(f, "{},{}", self.rows, self.cols)
+        }
+    }
+}
+
+/// Total distance between all galaxy pairs as a function of the expansion
+/// factors: `base + (rows - 1) * row_crossings + (cols - 1) * col_crossings`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Coefficients {
+    /// Total distance without any expansion.
+    base: i64,
+    /// Weight of the rows crossed, summed over all pairs.
+    row_crossings: i64,
+    /// Weight of the columns crossed, summed over all pairs.
+    col_crossings: i64,
+}
+
+impl Coefficients {
+    fn total(&self, factors: Factors) -> i64 {
+        self.base
+            + (factors.rows as i64 - 1) * self.row_crossings
+            + (factors.cols as i64 - 1) * self.col_crossings
+    }
+}
+
 struct Universe {
     space: Vec<Vec<Element>>,
-    expansion_factor: usize,
+    /// How many times each row expands: a row of weight `w` grows to
+    /// `1 + w * (factor - 1)` rows. Empty rows weigh 1 and the others 0,
+    /// unless replaced by `set_row_weights`.
+    row_weights: Vec<usize>,
+    /// Same as `row_weights`, for the columns.
+    col_weights: Vec<usize>,
 }
 
 impl Display for Universe {
@@ -62,27 +141,132 @@ impl Universe {
             space.push(row);
         }
 
-        Ok(Self {
+        Ok(Self::new(space))
+    }
+
+    fn new(space: Vec<Vec<Element>>) -> Self {
+        let mut row_weights = vec![1; space.len()];
+        let mut col_weights = vec![1; space[0].len()];
+
+        for (y, line) in space.iter().enumerate() {
+            for (x, element) in line.iter().enumerate() {
+                if matches!(element, Element::Galaxy) {
+                    row_weights[y] = 0;
+                    col_weights[x] = 0;
+                }
+            }
+        }
+
+        Self {
             space,
-            expansion_factor: 2,
-        })
+            row_weights,
+            col_weights,
+        }
+    }
+
+    fn set_row_weights(&mut self, weights: Vec<usize>) -> Result<(), Error> {
+        if weights.len() != self.row_weights.len() {
+            bail!(
+                "expected {} row weights, got {}",
+                self.row_weights.len(),
+                weights.len()
+            );
+        }
+        self.row_weights = weights;
+        Ok(())
     }
 
-    fn distances(&self) -> i64 {
-        let mut rows: Vec<bool> = vec![true; self.space.len()];
-        let mut cols: Vec<bool> = vec![true; self.space[0].len()];
+    fn set_col_weights(&mut self, weights: Vec<usize>) -> Result<(), Error> {
+        if weights.len() != self.col_weights.len() {
+            bail!(
+                "expected {} column weights, got {}",
+                self.col_weights.len(),
+                weights.len()
+            );
+        }
+        self.col_weights = weights;
+        Ok(())
+    }
+
+    /// Positions of the galaxies in reading order, as (row, column).
+    fn galaxies(&self) -> Vec<(usize, usize)> {
         let mut galaxies = Vec::new();
 
         for (y, line) in self.space.iter().enumerate() {
             for (x, element) in line.iter().enumerate() {
                 if matches!(element, Element::Galaxy) {
                     galaxies.push((y, x));
-                    rows[y] = false;
-                    cols[x] = false;
                 }
             }
         }
 
+        galaxies
+    }
+
+    /// Position of every galaxy after the rows and columns have expanded by
+    /// `factors`, in reading order.
+    ///
+    /// Prefix sums over the row and column weights give the expansion before
+    /// each row and column, so every position is computed once.
+    fn expanded_galaxies(&self, factors: Factors) -> Vec<(i64, i64)> {
+        let expand = |weights: &[usize], factor: usize| -> Vec<i64> {
+            let growth = factor as i64 - 1;
+            let mut weight_before = 0;
+            weights
+                .iter()
+                .enumerate()
+                .map(|(index, weight)| {
+                    let position = index as i64 + weight_before * growth;
+                    weight_before += *weight as i64;
+                    position
+                })
+                .collect()
+        };
+        let rows = expand(&self.row_weights, factors.rows);
+        let cols = expand(&self.col_weights, factors.cols);
+
+        self.galaxies()
+            .into_iter()
+            .map(|(y, x)| (rows[y], cols[x]))
+            .collect()
+    }
+
+    /// Sum of the distances between every pair of galaxies, with the rows
+    /// and columns expanded by `factors`.
+    ///
+    /// The Manhattan distance splits into the two axes, and along one axis
+    /// the i-th smallest of the sorted coordinates is the larger one in i
+    /// pairs and the smaller one in the rest. That makes the sum O(g log g)
+    /// in the number of galaxies.
+    fn distances(&self, factors: Factors) -> i64 {
+        let galaxies = self.expanded_galaxies(factors);
+
+        let mut ys: Vec<i64> = galaxies.iter().map(|galaxy| galaxy.0).collect();
+        let mut xs: Vec<i64> = galaxies.iter().map(|galaxy| galaxy.1).collect();
+
+        axis_distances(&mut ys) + axis_distances(&mut xs)
+    }
+
+    /// Splits the total distance into the part that does not depend on the
+    /// expansion factors and the parts that grow with them, so that any
+    /// number of factors can be evaluated with `Coefficients::total`.
+    fn coefficients(&self) -> Coefficients {
+        let base = self.distances(Factors::uniform(1));
+        let rows = Factors { rows: 2, cols: 1 };
+        let cols = Factors { rows: 1, cols: 2 };
+
+        Coefficients {
+            base,
+            row_crossings: self.distances(rows) - base,
+            col_crossings: self.distances(cols) - base,
+        }
+    }
+
+    /// Pairwise implementation of `distances`, which rescans the rows and
+    /// columns between every two galaxies.
+    #[cfg(test)]
+    fn distances_pairwise(&self, factors: Factors) -> i64 {
+        let galaxies = self.galaxies();
         let mut sum = 0;
 
         for i in 0..galaxies.len() - 1 {
@@ -95,19 +279,14 @@ impl Universe {
                 let min_x = first.1.min(second.1);
                 let max_x = first.1.max(second.1);
 
-                let empty_cols =
-                    (min_y + 1..max_y)
-                        .fold(0, |acc, index| if rows[index] { acc + 1 } else { acc });
-
-                let empty_rows =
-                    (min_x + 1..max_x)
-                        .fold(0, |acc, index| if cols[index] { acc + 1 } else { acc });
+                let row_weight: usize = self.row_weights[min_y..max_y].iter().sum();
+                let col_weight: usize = self.col_weights[min_x..max_x].iter().sum();
 
                 let diff_x = max_x as i64 - min_x as i64;
                 let diff_y = max_y as i64 - min_y as i64;
 
-                let expand_y = empty_rows * (self.expansion_factor - 1);
-                let expand_x = empty_cols * (self.expansion_factor - 1);
+                let expand_y = row_weight * (factors.rows - 1);
+                let expand_x = col_weight * (factors.cols - 1);
                 let distance = diff_x + diff_y + expand_x as i64 + expand_y as i64;
 
                 sum += distance;
@@ -118,14 +297,185 @@ impl Universe {
     }
 }
 
+/// Sum of the differences between every pair of coordinates on one axis.
+fn axis_distances(coordinates: &mut [i64]) -> i64 {
+    coordinates.sort_unstable();
+
+    let mut sum = 0;
+    let mut before = 0;
+    for (i, coordinate) in coordinates.iter().enumerate() {
+        sum += coordinate * i as i64 - before;
+        before += coordinate;
+    }
+
+    sum
+}
+
 fn main() -> Result<(), Error> {
-    let file = std::env::args().nth(1).expect("no input file provided");
+    let mut args = std::env::args().skip(1);
+    let file = args.next().context("no input file provided")?;
+
+    let mut factors: Vec<Factors> = Vec::new();
+    let mut row_weights = None;
+    let mut col_weights = None;
+    let mut metrics: Vec<Metric> = Vec::new();
+    let mut mean = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--factor" => factors.push(args.next().context("missing factor")?.parse()?),
+            "--row-weights" => {
+                row_weights = Some(parse_weights(&args.next().context("missing weights")?)?)
+            }
+            "--col-weights" => {
+                col_weights = Some(parse_weights(&args.next().context("missing weights")?)?)
+            }
+            "--metric" => metrics.push(args.next().context("missing metric")?.parse()?),
+            "--mean" => mean = true,
+            _ => bail!("unknown argument {arg}"),
+        }
+    }
 
     let mut universe = Universe::from_file(file)?;
+    if let Some(weights) = row_weights {
+        universe.set_row_weights(weights)?;
+    }
+    if let Some(weights) = col_weights {
+        universe.set_col_weights(weights)?;
+    }
+    let coefficients = universe.coefficients();
+
+    if !metrics.is_empty() {
+        let galaxies = universe.galaxies().len();
+        let pairs = galaxies * galaxies.saturating_sub(1) / 2;
+
+        let factors = if factors.is_empty() {
+            vec![Factors::uniform(2)]
+        } else {
+            factors
+        };
+
+        for factors in factors {
+            for metric in &metrics {
+                let sum = universe.metric_distances(factors, *metric);
+                print!("Factor {factors}, {metric:?}: {sum}");
+                if mean {
+                    match sum.mean(pairs) {
+                        Ok(ratio) => print!(", mean {ratio} ({:.6})", ratio.as_f64()),
+                        Err(_) => print!(", mean {:.6}", sum.as_f64() / pairs as f64),
+                    }
+                }
+                println!();
+            }
+        }
+        return Ok(());
+    }
+
+    if factors.is_empty() {
+        println!("Part 1: {}", coefficients.total(Factors::uniform(2)));
+        println!("Part 2: {}", coefficients.total(Factors::uniform(1000000)));
+        return Ok(());
+    }
 
-    println!("Part 1: {}", universe.distances());
-    universe.expansion_factor = 1000000;
-    println!("Part 2: {}", universe.distances());
+    println!(
+        "Base: {}, row crossings: {}, column crossings: {}",
+        coefficients.base, coefficients.row_crossings, coefficients.col_crossings
+    );
+    for factor in factors {
+        println!("Factor {factor}: {}", coefficients.total(factor));
+    }
 
     Ok(())
 }
+
+/// Parses a comma separated list of weights.
+fn parse_weights(list: &str) -> Result<Vec<usize>, Error> {
+    list.split(',')
+        .map(|weight| {
+            weight
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid weight {weight}"))
+        })
+        .collect()
+}
+
+/// Universe of the given size with `galaxies` galaxies at pseudo-random
+/// positions.
+#[cfg(test)]
+fn generate(rows: usize, cols: usize, galaxies: usize, seed: u64) -> Universe {
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    let mut next = |n: usize| {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % n as u64) as usize
+    };
+
+    let mut space = vec![vec![Element::Empty; cols]; rows];
+    let mut placed = 0;
+    while placed < galaxies {
+        let (y, x) = (next(rows), next(cols));
+        if matches!(space[y][x], Element::Empty) {
+            space[y][x] = Element::Galaxy;
+            placed += 1;
+        }
+    }
+
+    Universe::new(space)
+}
+
+#[test]
+fn test_distances() {
+    let universe = Universe::from_file("example").unwrap();
+    let coefficients = universe.coefficients();
+    assert_eq!(coefficients.base, 292);
+    assert_eq!(coefficients.row_crossings + coefficients.col_crossings, 82);
+    for (factor, expected) in [(2, 374), (10, 1030), (100, 8410)] {
+        let factors = Factors::uniform(factor);
+        assert_eq!(universe.distances(factors), expected);
+        assert_eq!(universe.distances_pairwise(factors), expected);
+    }
+
+    let factors: [Factors; 4] = ["1", "2", "1000000", "3,7"].map(|s| s.parse().unwrap());
+    for seed in 0..20 {
+        let mut universe = generate(40, 60, 1 + seed as usize * 3, seed);
+        if seed % 2 == 1 {
+            let rows = (0..40).map(|row| row % 3).collect();
+            let cols = (0..60).map(|col| (col * 7) % 5).collect();
+            universe.set_row_weights(rows).unwrap();
+            universe.set_col_weights(cols).unwrap();
+        }
+
+        let coefficients = universe.coefficients();
+        for factors in factors {
+            assert_eq!(
+                universe.distances(factors),
+                universe.distances_pairwise(factors)
+            );
+            assert_eq!(coefficients.total(factors), universe.distances(factors));
+        }
+    }
+
+    assert!(Factors::from_str("0,2").is_err());
+    let mut universe = Universe::from_file("example").unwrap();
+    assert!(universe.set_row_weights(vec![1; 3]).is_err());
+}
+
+#[test]
+#[ignore = "benchmark, run with `cargo test --release -- --ignored --nocapture`"]
+fn bench_distances() {
+    use std::time::Instant;
+
+    let universe = generate(3000, 3000, 10_000, 42);
+
+    let now = Instant::now();
+    let sum = universe.distances(Factors::uniform(1_000_000));
+    println!("sorted: {sum} in {:?}", now.elapsed());
+
+    let now = Instant::now();
+    let pairwise = universe.distances_pairwise(Factors::uniform(1_000_000));
+    println!("pairwise: {pairwise} in {:?}", now.elapsed());
+
+    assert_eq!(sum, pairwise);
+}
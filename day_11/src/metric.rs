@@ -0,0 +1,196 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{bail, Error};
+
+use crate::{axis_distances, Factors, Universe};
+
+/// Distance between two galaxies in the expanded universe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Manhattan,
+    Chebyshev,
+    Euclidean,
+    SquaredEuclidean,
+}
+
+impl FromStr for Metric {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "manhattan" => Self::Manhattan,
+            "chebyshev" => Self::Chebyshev,
+            "euclidean" => Self::Euclidean,
+            "squared-euclidean" => Self::SquaredEuclidean,
+            _ => bail!(
+                "invalid metric {s}, expected manhattan, chebyshev, euclidean or squared-euclidean"
+            ),
+        })
+    }
+}
+
+/// Sum of a metric over all galaxy pairs. Euclidean distances are
+/// irrational in general, so only their sum is approximate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sum {
+    Exact(i128),
+    Approximate(f64),
+}
+
+impl Sum {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Sum::Exact(sum) => sum as f64,
+            Sum::Approximate(sum) => sum,
+        }
+    }
+
+    /// Mean over `pairs` pairs as a reduced fraction, if the sum is exact.
+    pub fn mean(self, pairs: usize) -> Result<Ratio, Error> {
+        match self {
+            Sum::Exact(sum) if pairs > 0 => Ok(Ratio::new(sum, pairs as i128)),
+            Sum::Exact(_) => bail!("there are no pairs to average over"),
+            Sum::Approximate(_) => bail!("an approximate sum has no exact mean"),
+        }
+    }
+}
+
+impl Display for Sum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sum::Exact(sum) => write!(f, "{sum}"),
+            Sum::Approximate(sum) => write!(f, "{sum:.6}"),
+        }
+    }
+}
+
+/// Fraction in lowest terms with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ratio {
+    pub numerator: i128,
+    pub denominator: i128,
+}
+
+impl Ratio {
+    fn new(numerator: i128, denominator: i128) -> Self {
+        let gcd = gcd(numerator.abs(), denominator.abs()).max(1) * denominator.signum();
+        Self {
+            numerator: numerator / gcd,
+            denominator: denominator / gcd,
+        }
+    }
+
+    pub fn as_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+impl Display for Ratio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Universe {
+    /// Sum of `metric` over every pair of galaxies, with the rows and
+    /// columns expanded by `factors`.
+    ///
+    /// Chebyshev distances become Manhattan distances, halved, in
+    /// coordinates rotated by 45 degrees, and squared Euclidean ones split
+    /// into the axes as `n * sum(x²) - sum(x)²`, so both avoid visiting
+    /// every pair. Euclidean distances do not split and are summed pair by
+    /// pair in floating point.
+    pub fn metric_distances(&self, factors: Factors, metric: Metric) -> Sum {
+        let galaxies = self.expanded_galaxies(factors);
+
+        match metric {
+            Metric::Manhattan => Sum::Exact(self.distances(factors) as i128),
+            Metric::Chebyshev => {
+                let mut us: Vec<i64> = galaxies.iter().map(|(y, x)| y + x).collect();
+                let mut vs: Vec<i64> = galaxies.iter().map(|(y, x)| y - x).collect();
+                Sum::Exact((axis_distances(&mut us) + axis_distances(&mut vs)) as i128 / 2)
+            }
+            Metric::SquaredEuclidean => {
+                let n = galaxies.len() as i128;
+                let axis = |coordinates: &mut dyn Iterator<Item = i64>| {
+                    let (sum, squares) = coordinates.fold((0i128, 0i128), |(sum, squares), c| {
+                        (sum + c as i128, squares + c as i128 * c as i128)
+                    });
+                    n * squares - sum * sum
+                };
+                let ys = axis(&mut galaxies.iter().map(|galaxy| galaxy.0));
+                let xs = axis(&mut galaxies.iter().map(|galaxy| galaxy.1));
+                Sum::Exact(ys + xs)
+            }
+            Metric::Euclidean => {
+                let mut sum = 0.0;
+                for (i, a) in galaxies.iter().enumerate() {
+                    for b in &galaxies[i + 1..] {
+                        sum += ((a.0 - b.0) as f64).hypot((a.1 - b.1) as f64);
+                    }
+                }
+                Sum::Approximate(sum)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_metrics() {
+    let factors: [Factors; 3] = ["2", "1000000", "3,7"].map(|s| s.parse().unwrap());
+
+    for seed in 0..10 {
+        let universe = crate::generate(30, 40, 2 + seed as usize * 4, seed);
+
+        for factors in factors {
+            let galaxies = universe.expanded_galaxies(factors);
+            let mut expected = [0i128; 4];
+            let mut euclidean = 0.0;
+            for (i, a) in galaxies.iter().enumerate() {
+                for b in &galaxies[i + 1..] {
+                    let (dy, dx) = ((a.0 - b.0).abs() as i128, (a.1 - b.1).abs() as i128);
+                    expected[0] += dy + dx;
+                    expected[1] += dy.max(dx);
+                    expected[2] += dy * dy + dx * dx;
+                    euclidean += ((dy * dy + dx * dx) as f64).sqrt();
+                }
+            }
+
+            for (metric, expected) in [
+                Metric::Manhattan,
+                Metric::Chebyshev,
+                Metric::SquaredEuclidean,
+            ]
+            .into_iter()
+            .zip(expected)
+            {
+                assert_eq!(
+                    universe.metric_distances(factors, metric),
+                    Sum::Exact(expected)
+                );
+            }
+
+            let sum = universe.metric_distances(factors, Metric::Euclidean);
+            assert!((sum.as_f64() - euclidean).abs() <= 1e-9 * euclidean);
+            assert!(sum.mean(1).is_err());
+        }
+    }
+
+    let universe = crate::Universe::from_file("example").unwrap();
+    let sum = universe.metric_distances(Factors::uniform(2), Metric::Manhattan);
+    assert_eq!(sum, Sum::Exact(374));
+    assert_eq!(sum.mean(36).unwrap().to_string(), "187/18");
+    assert_eq!(Ratio::new(-4, -6).to_string(), "2/3");
+}